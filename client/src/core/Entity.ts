@@ -1,4 +1,4 @@
-import type { Inventory } from "@type";
+import type { Inventory, Item } from "@type";
 import type { Vec2 } from "./Vec2";
 
 export class Entity {
@@ -12,12 +12,19 @@ export class EntityPlayer extends Entity {
     username: string;
     skin: number;
     inventory: Inventory;
+    hunger: number;
 
     previous_position: Vec2;
     direction: number;
     animation_frame: number;
 }
 
+export class EntityDroppedItem extends Entity {
+    entity_type: 'dropped_item';
+    item: Item;
+    count: number;
+}
+
 export class EntityForestTree extends Entity {
     entity_type: 'forest_tree';
 }
@@ -66,9 +73,26 @@ export class EntitySeaShell extends Entity {
     entity_type: 'sea_shell';
 }
 
+export class EntityWolf extends Entity {
+    entity_type: 'wolf';
+}
+
+export class EntityScorpion extends Entity {
+    entity_type: 'scorpion';
+}
+
+export class EntityPolarBear extends Entity {
+    entity_type: 'polar_bear';
+}
+
+export class EntityCrab extends Entity {
+    entity_type: 'crab';
+}
+
 
 export type EntityType =
     EntityPlayer |
+    EntityDroppedItem |
     EntityForestTree |
     EntitySpruceTree |
     EntityJungleTree |
@@ -80,4 +104,8 @@ export type EntityType =
     EntityBigStone |
     EntityTreeLog |
     EntityTallGrass |
-    EntitySeaShell;
+    EntitySeaShell |
+    EntityWolf |
+    EntityScorpion |
+    EntityPolarBear |
+    EntityCrab;
@@ -0,0 +1,168 @@
+// Just enough MessagePack for the packets exchanged with the server.
+// The server sends UUIDs as 16 byte binaries, they are decoded to their string form.
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+export function encodeMessagePack(value: unknown): Uint8Array {
+    const bytes: number[] = [];
+    write(bytes, value);
+    return new Uint8Array(bytes);
+}
+
+export function decodeMessagePack(data: ArrayBuffer): any {
+    const reader = new Reader(new DataView(data));
+    return reader.read();
+}
+
+function write(bytes: number[], value: unknown) {
+    if (value === null || value === undefined) {
+        bytes.push(0xc0);
+    } else if (typeof value === 'boolean') {
+        bytes.push(value ? 0xc3 : 0xc2);
+    } else if (typeof value === 'number') {
+        writeNumber(bytes, value);
+    } else if (typeof value === 'string') {
+        const encoded = textEncoder.encode(value);
+        writeHeader(bytes, encoded.length, 0xa0, 31, [0xd9, 0xda, 0xdb]);
+        bytes.push(...encoded);
+    } else if (Array.isArray(value)) {
+        writeHeader(bytes, value.length, 0x90, 15, [null, 0xdc, 0xdd]);
+        for (const item of value) {
+            write(bytes, item);
+        }
+    } else if (typeof value === 'object') {
+        const entries = Object.entries(value).filter(([, v]) => v !== undefined);
+        writeHeader(bytes, entries.length, 0x80, 15, [null, 0xde, 0xdf]);
+        for (const [key, item] of entries) {
+            write(bytes, key);
+            write(bytes, item);
+        }
+    } else {
+        throw new Error(`Cannot encode ${typeof value}`);
+    }
+}
+
+function writeNumber(bytes: number[], value: number) {
+    const view = new DataView(new ArrayBuffer(8));
+
+    if (Number.isInteger(value) && value >= -0x80000000 && value <= 0xffffffff) {
+        if (value >= 0 && value <= 0x7f) {
+            bytes.push(value);
+        } else if (value < 0 && value >= -32) {
+            bytes.push(value & 0xff);
+        } else if (value < 0) {
+            view.setInt32(0, value);
+            bytes.push(0xd2, ...new Uint8Array(view.buffer, 0, 4));
+        } else {
+            view.setUint32(0, value);
+            bytes.push(0xce, ...new Uint8Array(view.buffer, 0, 4));
+        }
+    } else {
+        view.setFloat64(0, value);
+        bytes.push(0xcb, ...new Uint8Array(view.buffer));
+    }
+}
+
+// `markers` are the 8, 16 and 32 bit length variants, `null` where the type has none
+function writeHeader(bytes: number[], length: number, fix: number, fixMax: number, markers: (number | null)[]) {
+    if (length <= fixMax) {
+        bytes.push(fix | length);
+    } else if (length <= 0xff && markers[0] !== null) {
+        bytes.push(markers[0]!, length);
+    } else if (length <= 0xffff) {
+        bytes.push(markers[1]!, length >> 8, length & 0xff);
+    } else {
+        bytes.push(markers[2]!, length >>> 24, (length >> 16) & 0xff, (length >> 8) & 0xff, length & 0xff);
+    }
+}
+
+class Reader {
+    private offset = 0;
+
+    constructor(private view: DataView) {}
+
+    read(): any {
+        const marker = this.u8();
+
+        if (marker <= 0x7f) return marker;
+        if (marker >= 0xe0) return marker - 0x100;
+        if ((marker & 0xf0) === 0x80) return this.map(marker & 0x0f);
+        if ((marker & 0xf0) === 0x90) return this.array(marker & 0x0f);
+        if ((marker & 0xe0) === 0xa0) return this.str(marker & 0x1f);
+
+        switch (marker) {
+            case 0xc0: return null;
+            case 0xc2: return false;
+            case 0xc3: return true;
+            case 0xc4: return this.bin(this.u8());
+            case 0xc5: return this.bin(this.u16());
+            case 0xc6: return this.bin(this.u32());
+            case 0xca: return this.step(4, this.view.getFloat32(this.offset));
+            case 0xcb: return this.step(8, this.view.getFloat64(this.offset));
+            case 0xcc: return this.u8();
+            case 0xcd: return this.u16();
+            case 0xce: return this.u32();
+            case 0xcf: return this.step(8, Number(this.view.getBigUint64(this.offset)));
+            case 0xd0: return this.step(1, this.view.getInt8(this.offset));
+            case 0xd1: return this.step(2, this.view.getInt16(this.offset));
+            case 0xd2: return this.step(4, this.view.getInt32(this.offset));
+            case 0xd3: return this.step(8, Number(this.view.getBigInt64(this.offset)));
+            case 0xd9: return this.str(this.u8());
+            case 0xda: return this.str(this.u16());
+            case 0xdb: return this.str(this.u32());
+            case 0xdc: return this.array(this.u16());
+            case 0xdd: return this.array(this.u32());
+            case 0xde: return this.map(this.u16());
+            case 0xdf: return this.map(this.u32());
+        }
+
+        throw new Error(`Unsupported MessagePack marker 0x${marker.toString(16)}`);
+    }
+
+    private step<T>(size: number, value: T): T {
+        this.offset += size;
+        return value;
+    }
+
+    private u8(): number {
+        return this.step(1, this.view.getUint8(this.offset));
+    }
+
+    private u16(): number {
+        return this.step(2, this.view.getUint16(this.offset));
+    }
+
+    private u32(): number {
+        return this.step(4, this.view.getUint32(this.offset));
+    }
+
+    private str(length: number): string {
+        const bytes = new Uint8Array(this.view.buffer, this.view.byteOffset + this.offset, length);
+        return this.step(length, textDecoder.decode(bytes));
+    }
+
+    private bin(length: number): Uint8Array | string {
+        const bytes = new Uint8Array(this.view.buffer.slice(this.offset, this.offset + length));
+        this.offset += length;
+        return length === 16 ? uuidToString(bytes) : bytes;
+    }
+
+    private array(length: number): any[] {
+        return Array.from({ length }, () => this.read());
+    }
+
+    private map(length: number): Record<string, any> {
+        const map: Record<string, any> = {};
+        for (let i = 0; i < length; i++) {
+            const key = this.read();
+            map[key] = this.read();
+        }
+        return map;
+    }
+}
+
+function uuidToString(bytes: Uint8Array): string {
+    const hex = Array.from(bytes, byte => byte.toString(16).padStart(2, '0')).join('');
+    return `${hex.slice(0, 8)}-${hex.slice(8, 12)}-${hex.slice(12, 16)}-${hex.slice(16, 20)}-${hex.slice(20)}`;
+}
@@ -10,3 +10,4 @@ export * from './Texture';
 export * from './Sprite';
 export * from './Transform';
 export * from './utils';
+export * from './MessagePack';
@@ -1,21 +1,40 @@
 import {
     type Packet,
     type PlayerRegisteredPacket,
-    type TerrainChunkPacket,
+    type TerrainChunkBatchPacket,
+    type TerrainChunkUnloadPacket,
     type TerrainChunk,
     type EntityLoadPacket,
     type EntityMovePacket,
+    type EntityMoveBatchPacket,
     type ChatMessagePacket,
     type SystemMessagePacket,
     type TerrainTileType,
     type EntityUnloadPacket,
     type EntityDamagePacket,
     type EntityDeathPacket,
-    itemToNumber
+    type InventoryStatePacket,
+    type RecipeListPacket,
+    type PlayerDeathPacket,
+    type PlayerStatsPacket,
+    type Recipe,
+    type GameSettings,
+    itemToNumber,
+    itemMaxDurability,
+    terrainTileTypeFromNumber
 } from '@type';
 
 import { Renderer } from '@render';
-import { Color, Entity, EntityPlayer, Mat3, Vec2, type EntityType } from '@core';
+import {
+    Color,
+    Entity,
+    EntityPlayer,
+    Mat3,
+    Vec2,
+    decodeMessagePack,
+    encodeMessagePack,
+    type EntityType
+} from '@core';
 
 import { TerrainShader } from '@render/shaders/terrain/TerrainShader';
 import { HitboxShader } from '@render/shaders/hitbox/HitboxShader';
@@ -35,12 +54,23 @@ const DIRECTION_NW = iota++;
 
 const ENTITY_SIZE = 0.75;
 
+const RECONNECT_DELAY = 1000;
+const MAX_RECONNECT_ATTEMPTS = 25;
+
+// Packets are MessagePack unless the page is opened with `?encoding=json`, which is easier to debug
+const ENCODING = new URLSearchParams(document.location.search).get('encoding') === 'json' ? 'json' : 'msgpack';
+
 const NON_WALKABLE_TILES = new Set<TerrainTileType>([
     "Water",
     "DeepWater",
 ]);
 
 const HP_CRITICAL_THRESHOLD = 20;
+const HUNGER_CRITICAL_THRESHOLD = 20;
+
+const PICKUP_RANGE = 2;
+
+const PLAYER_SPEED = 8;
 
 const HOTBAR_SIZE = 6;
 const numericCodes = {
@@ -87,9 +117,21 @@ class Game {
     private healthBarShader: HealthBarShader;
 
     private playerId?: string;
+    private resumeToken: string | null = null;
+    private settings: GameSettings | null = null;
+    private reconnectAttempts = 0;
     private entities: Map<string, EntityType> = new Map();
+    private recipes: Recipe[] = [];
     private terrain: Map<string, TerrainChunk> = new Map();
 
+    private spectatorPosition: Vec2 | null = null;
+    private canSpectate = false;
+    private respawnTimer: number | null = null;
+
+    private deathScreen = document.getElementById("game__death") as HTMLDivElement;
+    private deathKiller = document.getElementById("game__death__killer") as HTMLSpanElement;
+    private respawnButton = document.getElementById("game__death__respawn") as HTMLButtonElement;
+
     private lastLoopTimestamp: number;
 
     // TODO: remove?
@@ -141,15 +183,33 @@ class Game {
             this.chatInput.value = "";
         });
 
+        this.respawnButton.addEventListener("click", () => {
+            this.send({ packet_type: "player_respawn" });
+        });
+
         this.tilemap_items = new Image();
         this.tilemap_items.src = tilemap_items_src;
 
         this.ws = ws;
     }
 
-    static async create(username: string, skin: number): Promise<Game> {
+    private static socketUrl(): string {
+        return `${document.location.protocol == 'https:' ? 'wss' : 'ws'}://${document.location.host}/ws?encoding=${ENCODING}`;
+    }
+
+    private static openSocket(): WebSocket {
+        const ws = new WebSocket(Game.socketUrl());
+        ws.binaryType = 'arraybuffer';
+        return ws;
+    }
+
+    private static decode(data: string | ArrayBuffer): any {
+        return typeof data === 'string' ? JSON.parse(data) : decodeMessagePack(data);
+    }
+
+    static async create(token: string, skin: number, freshStart: boolean): Promise<Game> {
         return new Promise((resolve, reject) => {
-            const ws = new WebSocket(`${document.location.protocol == 'https:' ? 'wss' : 'ws'}://${document.location.host}/ws`);
+            const ws = Game.openSocket();
             const pathElements = document.location.pathname.split('/');
             const gameName = pathElements[pathElements.length - 1];
 
@@ -164,13 +224,14 @@ class Game {
                     packet_type: 'player_register',
                     game_name: gameName,
 
-                    username,
+                    token,
                     skin,
+                    fresh_start: freshStart,
                 });
             };
 
             ws.onmessage = ev => {
-                const data = JSON.parse(ev.data);
+                const data = Game.decode(ev.data);
                 if (data.error) {
                     ws.close();
                     reject(new Error(data.error));
@@ -180,6 +241,7 @@ class Game {
                 game.recv(packet);
 
                 if (packet.packet_type === 'player_registered') {
+                    game.attach(ws);
                     resolve(game);
                 }
             };
@@ -189,6 +251,50 @@ class Game {
         })
     }
 
+    private attach(ws: WebSocket) {
+        this.ws = ws;
+
+        ws.onmessage = ev => {
+            const data = Game.decode(ev.data);
+            if (data.error) {
+                alert(`Connection lost: ${data.error}`);
+                window.location.reload();
+                return;
+            }
+
+            this.recv(this.parsePacket(data));
+        };
+
+        ws.onclose = () => {
+            if (this.reconnectAttempts >= MAX_RECONNECT_ATTEMPTS) {
+                alert('Connection lost');
+                window.location.reload();
+                return;
+            }
+
+            this.reconnectAttempts++;
+            setTimeout(() => this.reconnect(), RECONNECT_DELAY);
+        };
+        ws.onerror = console.error;
+    }
+
+    private reconnect() {
+        const ws = Game.openSocket();
+
+        ws.onopen = () => {
+            // The server sends everything in view again
+            this.entities.clear();
+            this.terrain.clear();
+
+            this.send({
+                packet_type: 'player_resume',
+                resume_token: this.resumeToken!,
+            });
+        };
+
+        this.attach(ws);
+    }
+
     private updateHpCriticalState() {
         const hp = this.entities.get(this.playerId!)?.health;
         if (!hp) return;
@@ -205,25 +311,61 @@ class Game {
         }
     }
 
+    private updateHungerState() {
+        const hunger = this.getPlayer()?.hunger;
+        if (hunger === undefined) return;
+
+        const hungerValueSpan = document.getElementById("game__hud__hunger_value") as HTMLSpanElement;
+        const hungerDiv = document.getElementById("game__hud__hunger") as HTMLDivElement;
+
+        hungerValueSpan.textContent = hunger.toString();
+
+        if (hunger <= HUNGER_CRITICAL_THRESHOLD) {
+            hungerDiv.classList.add("game__hud__hp--critical");
+        } else {
+            hungerDiv.classList.remove("game__hud__hp--critical");
+        }
+    }
+
+    // Chunks arrive as a palette of tiles and runs of `palette index, run length` pairs
+    private parseChunk(chunkData: any): TerrainChunk {
+        // Binary encodings send tiles by index
+        const palette: TerrainTileType[] = chunkData['palette'].map((tile: TerrainTileType | number) =>
+            typeof tile === 'number' ? terrainTileTypeFromNumber(tile) : tile
+        );
+        const runs: number[] = chunkData['runs'];
+
+        const contents: TerrainTileType[] = [];
+        for (let i = 0; i + 1 < runs.length; i += 2) {
+            for (let j = 0; j < runs[i + 1]; j++) {
+                contents.push(palette[runs[i]]);
+            }
+        }
+
+        return {
+            position: Vec2.from(chunkData['position']),
+            contents,
+        };
+    }
+
     private parsePacket(data: any): Packet {
         const packet_type = data['packet_type'];
 
         switch (packet_type) {
             case 'player_registered': return {
                 packet_type,
-                id: data['id']
+                id: data['id'],
+                resume_token: data['resume_token'],
+                settings: data['settings'],
+            };
+            case 'terrain_chunk_batch': return {
+                packet_type,
+                chunks: data['chunks'].map((chunkData: any) => this.parseChunk(chunkData)),
+            };
+            case 'terrain_chunk_unload': return {
+                packet_type,
+                chunks: data['chunks'].map((position: any) => Vec2.from(position)),
             };
-            case 'terrain_chunk': {
-                const chunkData = data['chunk'];
-
-                return {
-                    packet_type,
-                    chunk: {
-                        position: Vec2.from(chunkData['position']),
-                        contents: chunkData['contents']
-                    }
-                };
-            }
             case 'entity_load': {
                 const entityData = data['entity'];
 
@@ -274,6 +416,13 @@ class Game {
                 id: data['id'],
                 new_position: Vec2.from(data['new_position']),
             }
+            case 'entity_move_batch': return {
+                packet_type,
+                moves: data['moves'].map((move: any) => ({
+                    id: move['id'],
+                    new_position: Vec2.from(move['new_position']),
+                })),
+            }
             case 'chat_message': return {
                 packet_type,
                 id: data['id'],
@@ -293,6 +442,29 @@ class Game {
                 packet_type,
                 id: data['id'],
             }
+            case 'inventory_state': return {
+                packet_type,
+                slots: data['slots'],
+                selected: data['selected'],
+            }
+            case 'recipe_list': return {
+                packet_type,
+                recipes: data['recipes'],
+            }
+            case 'player_death': return {
+                packet_type,
+                killer: data['killer'],
+                respawn_cooldown: data['respawn_cooldown'],
+                can_spectate: data['can_spectate'],
+            }
+            case 'player_respawned': return {
+                packet_type,
+            }
+            case 'player_stats': return {
+                packet_type,
+                health: data['health'],
+                hunger: data['hunger'],
+            }
         }
 
         throw new Error(`Do not know how to parse packet of type "${packet_type}"`);
@@ -317,6 +489,12 @@ class Game {
         entity.position = packet.new_position;
     }
 
+    private onEntityMoveBatch(packet: EntityMoveBatchPacket) {
+        for (const move of packet.moves) {
+            this.onEntityMove({ packet_type: 'entity_move', ...move });
+        }
+    }
+
     private onEntityDamage(packet: EntityDamagePacket) {
         const ent = this.entities.get(packet.id);
         if (!ent) return;
@@ -331,11 +509,77 @@ class Game {
 
     private onEntityDeath(packet: EntityDeathPacket) {
         // TODO: death sound
+        if (packet.id === this.playerId) {
+            this.spectatorPosition = this.entities.get(packet.id)?.position ?? null;
+        }
+
         this.entities.delete(packet.id);
     }
 
+    private onPlayerDeath(packet: PlayerDeathPacket) {
+        this.canSpectate = packet.can_spectate;
+
+        this.deathKiller.textContent = packet.killer
+            ? `Killed by ${packet.killer}`
+            : "";
+        this.deathScreen.style.display = "flex";
+
+        let remaining = Math.ceil(packet.respawn_cooldown);
+        const updateButton = () => {
+            this.respawnButton.disabled = remaining > 0;
+            this.respawnButton.textContent = remaining > 0 ? `Respawn (${remaining})` : "Respawn";
+        };
+        updateButton();
+
+        if (this.respawnTimer !== null) clearInterval(this.respawnTimer);
+        this.respawnTimer = window.setInterval(() => {
+            remaining--;
+            updateButton();
+
+            if (remaining <= 0 && this.respawnTimer !== null) {
+                clearInterval(this.respawnTimer);
+                this.respawnTimer = null;
+            }
+        }, 1000);
+    }
+
+    private onPlayerRespawned() {
+        this.spectatorPosition = null;
+        this.deathScreen.style.display = "none";
+        this.updateHpCriticalState();
+        this.updateHungerState();
+    }
+
+    private onPlayerStats(packet: PlayerStatsPacket) {
+        const player = this.getPlayer();
+        if (!player) return;
+
+        player.health = packet.health;
+        player.hunger = packet.hunger;
+
+        this.updateHpCriticalState();
+        this.updateHungerState();
+    }
+
+    private onInventoryState(packet: InventoryStatePacket) {
+        const player = this.getPlayer();
+        if (!player) return;
+
+        player.inventory = {
+            slots: packet.slots,
+            selected: packet.selected,
+        };
+    }
+
+    private onRecipeList(packet: RecipeListPacket) {
+        this.recipes = packet.recipes;
+    }
+
     private onPlayerRegistered(packet: PlayerRegisteredPacket) {
         this.playerId = packet.id;
+        this.resumeToken = packet.resume_token;
+        this.settings = packet.settings;
+        this.reconnectAttempts = 0;
         const chatbox = document.getElementById('game__chat');
         if (chatbox) {
             chatbox.style.display = 'flex';
@@ -346,8 +590,16 @@ class Game {
         }
     }
 
-    private onTerrainChunk(packet: TerrainChunkPacket) {
-        this.terrain.set(`${packet.chunk.position.x}:${packet.chunk.position.y}`, packet.chunk);
+    private onTerrainChunkBatch(packet: TerrainChunkBatchPacket) {
+        for (const chunk of packet.chunks) {
+            this.terrain.set(`${chunk.position.x}:${chunk.position.y}`, chunk);
+        }
+    }
+
+    private onTerrainChunkUnload(packet: TerrainChunkUnloadPacket) {
+        for (const position of packet.chunks) {
+            this.terrain.delete(`${position.x}:${position.y}`);
+        }
     }
 
     private onChatMessage(packet: ChatMessagePacket) {
@@ -382,18 +634,26 @@ class Game {
             case 'entity_load': this.onEntityLoad(packet); break;
             case 'entity_unload': this.onEntityUnload(packet); break;
             case 'entity_move': this.onEntityMove(packet); break;
+            case 'entity_move_batch': this.onEntityMoveBatch(packet); break;
             case 'player_registered': this.onPlayerRegistered(packet); break;
-            case 'terrain_chunk': this.onTerrainChunk(packet); break;
+            case 'terrain_chunk_batch': this.onTerrainChunkBatch(packet); break;
+            case 'terrain_chunk_unload': this.onTerrainChunkUnload(packet); break;
             case 'chat_message': this.onChatMessage(packet); break;
             case 'system_message': this.onSystemMessage(packet); break;
             case 'entity_damage': this.onEntityDamage(packet); break;
             case 'entity_death': this.onEntityDeath(packet); break;
+            case 'inventory_state': this.onInventoryState(packet); break;
+            case 'recipe_list': this.onRecipeList(packet); break;
+            case 'player_death': this.onPlayerDeath(packet); break;
+            case 'player_respawned': this.onPlayerRespawned(); break;
+            case 'player_stats': this.onPlayerStats(packet); break;
             default: console.warn(`No handler found for packet of type "${packet.packet_type}"`);
         }
     }
 
     private send(packet: Packet) {
-        this.ws.send(JSON.stringify(packet));
+        if (this.ws.readyState !== WebSocket.OPEN) return;
+        this.ws.send(ENCODING === 'msgpack' ? encodeMessagePack(packet) : JSON.stringify(packet));
     }
 
     private getPlayer(): EntityPlayer | null {
@@ -445,10 +705,10 @@ class Game {
 
         if (!this.playerId) return;
 
-        const player = this.getPlayer();
-        if (!player) return;
+        const cameraPosition = this.getPlayer()?.position ?? this.spectatorPosition;
+        if (!cameraPosition) return;
 
-        this.renderer.setCameraPosition(player.position);
+        this.renderer.setCameraPosition(cameraPosition);
         this.renderer.setCameraScale(Number.parseFloat(this.scaleSlider.value!));
 
         // render
@@ -511,8 +771,12 @@ class Game {
 
     private getMaxHealth(entity: EntityType): number {
         switch (entity.entity_type) {
-            case 'player': return 250;
-            default: return 100;
+            case 'player': return this.settings?.max_player_health ?? 250;
+            case 'wolf': return 80;
+            case 'scorpion': return 60;
+            case 'polar_bear': return 200;
+            case 'crab': return 40;
+            default: return this.settings?.max_world_entity_health ?? 100;
         }
     }
 
@@ -551,19 +815,22 @@ class Game {
     private update(now: number) {
         if (!this.playerId) return;
 
-        const player = this.getPlayer();
-        if (!player) return;
-
         const dt = (now - this.lastLoopTimestamp) / 1000;
         this.lastLoopTimestamp = now;
 
+        const player = this.getPlayer();
+        if (!player) {
+            this.updateSpectator(dt);
+            return;
+        }
+
         if (now - this.lastFpsUpdate > 100) {
             this.fpsSpan.innerText = `FPS: ${Math.round(1 / dt)}`;
             this.lastFpsUpdate = now;
         }
         this.positionSpan.innerText = `x: ${Math.round(player.position.x)} y: ${Math.round(player.position.y)}`;
 
-        const speed = this.keyboardState["ShiftLeft"] ? 80 : 8;
+        const speed = PLAYER_SPEED;
 
         let dx = 0, dy = 0;
         if (this.keyboardState["KeyW"]) dy += 1;
@@ -575,34 +842,6 @@ class Game {
             const len = Math.hypot(dx, dy);
             dx /= len; dy /= len;
 
-            for (let loc of this.terrain.keys()) {
-                const [scx, scy] = loc.split(':');
-
-                const [cx, cy] = [
-                    Number.parseInt(scx),
-                    Number.parseInt(scy)
-                ];
-
-                const [x, y] = [
-                    cx * 8 + 4,
-                    cy * 8 + 4
-                ];
-
-                if (Math.hypot(player.position.x - x, player.position.y - y) > 100) {
-                    this.terrain.delete(loc);
-                }
-            }
-
-            for (let id of this.entities.keys()) {
-                const { entity_type, position: { x, y } } = this.entities.get(id)!;
-
-                if (entity_type == 'player') continue; // TODO: actually remove // TODO(server): recieve back upon load
-
-                if (Math.hypot(player.position.x - x, player.position.y - y) > 100) {
-                    this.entities.delete(id);
-                }
-            }
-
             dx *= dt * speed;
             dy *= dt * speed;
 
@@ -641,6 +880,33 @@ class Game {
         }
     }
 
+    private updateSpectator(dt: number) {
+        if (!this.spectatorPosition || !this.canSpectate) return;
+
+        let dx = 0, dy = 0;
+        if (this.keyboardState["KeyW"]) dy += 1;
+        if (this.keyboardState["KeyS"]) dy -= 1;
+        if (this.keyboardState["KeyA"]) dx -= 1;
+        if (this.keyboardState["KeyD"]) dx += 1;
+
+        if (!dx && !dy) return;
+
+        const len = Math.hypot(dx, dy);
+
+        this.spectatorPosition = new Vec2(
+            this.spectatorPosition.x + dx / len * dt * PLAYER_SPEED,
+            this.spectatorPosition.y + dy / len * dt * PLAYER_SPEED
+        );
+
+        this.positionSpan.innerText = `x: ${Math.round(this.spectatorPosition.x)} y: ${Math.round(this.spectatorPosition.y)}`;
+
+        this.send({
+            packet_type: 'entity_move',
+            id: this.playerId!,
+            new_position: this.spectatorPosition,
+        });
+    }
+
     animate() {
         for (let entity of this.entities.values()) {
             if (entity.entity_type === 'player') {
@@ -730,15 +996,103 @@ class Game {
 
             const slot = input.parentElement;
 
-            slot.querySelector('img')?.remove();
+            slot.querySelectorAll('img, span').forEach(el => el.remove());
+
+            const stack = player.inventory.slots[i];
+            if (!stack) continue;
+
+            const iconIndex = itemToNumber(stack.item);
+            if (iconIndex === null) {
+                const label = document.createElement('span');
+                label.textContent = stack.item;
+                slot.appendChild(label);
+            } else {
+                const img = this.tilemap_items.cloneNode(true) as HTMLImageElement;
+                img.style.transform = `translateX(-${100 * iconIndex / 3}%)`;
+
+                slot.appendChild(img);
+            }
+
+            if (stack.count > 1) {
+                const count = document.createElement('span');
+                count.classList.add('game__hud__inventory__slot__count');
+                count.textContent = stack.count.toString();
+                slot.appendChild(count);
+            }
+
+            const maxDurability = itemMaxDurability(stack.item);
+            if (stack.durability !== undefined && maxDurability !== null && stack.durability < maxDurability) {
+                const durability = document.createElement('span');
+                durability.classList.add('game__hud__inventory__slot__durability');
+                durability.style.width = `${100 * stack.durability / maxDurability}%`;
+                slot.appendChild(durability);
+            }
+        }
+    }
+
+    private setupInventoryDragAndDrop() {
+        for (let i = 0; i < HOTBAR_SIZE; i++) {
+            const input = document.getElementById(`slot_${i}`) as HTMLInputElement;
+            if (!input || !input.parentElement) continue;
+
+            const slot = input.parentElement;
+            slot.draggable = true;
+
+            slot.addEventListener('dragstart', ev => {
+                ev.dataTransfer?.setData('text/plain', i.toString());
+            });
+
+            slot.addEventListener('dragover', ev => ev.preventDefault());
+
+            slot.addEventListener('drop', ev => {
+                ev.preventDefault();
 
-            const item = player.inventory.slots[i];
-            if (!item) continue;
+                const from = Number.parseInt(ev.dataTransfer?.getData('text/plain') ?? '');
+                if (Number.isNaN(from) || from === i) return;
 
-            const img = this.tilemap_items.cloneNode(true) as HTMLImageElement;
-            img.style.transform = `translateX(-${100 * itemToNumber(item) / 3}%)`;
+                const stack = this.getPlayer()?.inventory.slots[from];
+                if (!stack) return;
 
-            slot.appendChild(img);
+                if (ev.shiftKey && stack.count > 1) {
+                    this.send({
+                        packet_type: 'inventory_split',
+                        from,
+                        to: i,
+                        count: Math.floor(stack.count / 2),
+                    });
+                } else {
+                    this.send({
+                        packet_type: 'inventory_move',
+                        from,
+                        to: i,
+                    });
+                }
+            });
+        }
+    }
+
+    private pickUpNearestItem() {
+        const player = this.getPlayer();
+        if (!player) return;
+
+        let nearest: string | null = null;
+        let nearestDistance = PICKUP_RANGE;
+
+        for (const entity of this.entities.values()) {
+            if (entity.entity_type !== 'dropped_item') continue;
+
+            const distance = Math.hypot(entity.position.x - player.position.x, entity.position.y - player.position.y);
+            if (distance <= nearestDistance) {
+                nearest = entity.id;
+                nearestDistance = distance;
+            }
+        }
+
+        if (nearest) {
+            this.send({
+                packet_type: 'item_pickup',
+                id: nearest,
+            });
         }
     }
 
@@ -747,6 +1101,8 @@ class Game {
 
         requestAnimationFrame(this.loop.bind(this));
 
+        this.setupInventoryDragAndDrop();
+
         window.addEventListener("keydown", e => {
             this.keyboardState[e.code] = true && !this.chatFocused;
 
@@ -765,6 +1121,20 @@ class Game {
                 this.chatInput.value = '/';
             }
 
+            if (e.code === 'KeyE') {
+                this.pickUpNearestItem();
+            }
+
+            if (e.code === 'KeyF') {
+                const player = this.getPlayer();
+                if (player) {
+                    this.send({
+                        packet_type: 'use_item',
+                        slot: player.inventory.selected,
+                    });
+                }
+            }
+
             if (e.code in numericCodes) {
                 this.updateItemSelection(numericCodes[e.code as keyof typeof numericCodes]);
             }
@@ -801,16 +1171,32 @@ class Game {
             this.updateItemSelection((cur + Math.sign(ev.deltaY) + HOTBAR_SIZE) % HOTBAR_SIZE);
         });
 
-        setInterval(() => this.ws.send(''), 20 * 1000);
+        setInterval(() => this.ws.readyState === WebSocket.OPEN && this.ws.send(''), 20 * 1000);
         setInterval(this.animate.bind(this), 100);
     }
 }
 
+async function authenticate(action: 'login' | 'register', username: string, password: string): Promise<string> {
+    const res = await fetch(`/${action}`, {
+        method: 'POST',
+        body: new URLSearchParams({ username, password }),
+    });
+
+    const data = await res.json();
+    if (!res.ok) {
+        throw new Error(data.error);
+    }
+
+    return data.token;
+}
+
 function startGame() {
     const formDiv = document.getElementById('form')! as HTMLDivElement;
     const gameDiv = document.getElementById('game')! as HTMLDivElement;
 
     const usernameInput = formDiv.querySelector('input[name=username]') as HTMLInputElement;
+    const passwordInput = formDiv.querySelector('input[name=password]') as HTMLInputElement;
+    const freshStartInput = formDiv.querySelector('input[name=fresh_start]') as HTMLInputElement;
     const skinInput = formDiv.querySelector('input[name=skin]') as HTMLInputElement;
 
     const form = formDiv.querySelector('form')!;
@@ -824,12 +1210,22 @@ function startGame() {
         }
 
         const skin = parseInt(skinInput.value);
+        const action = (ev.submitter as HTMLButtonElement | null)?.value === 'register' ? 'register' : 'login';
+
+        let token: string;
+        try {
+            token = await authenticate(action, username, passwordInput.value);
+            localStorage.setItem('session_token', token);
+        } catch (err: any) {
+            alert(`${action === 'register' ? 'Registration' : 'Login'} failed: ${err.message}`);
+            return;
+        }
 
         formDiv.remove();
         gameDiv.removeAttribute('style');
 
         try {
-            const game = await Game.create(username, skin);
+            const game = await Game.create(token, skin, freshStartInput.checked);
             game.run();
         } catch (err: any) {
             alert(`Registration failed: ${err.message}`);
@@ -34,6 +34,22 @@ const SPRITE_MAP: Map<string, { layer: number, x: number, y: number, w: number,
     [ 'tree_log',    { layer: 0, x: 9, y: 1, w: 1, h: 1 } ],
     [ 'tall_grass',  { layer: 0, x: 10, y: 1, w: 1, h: 1 } ],
     [ 'sea_shell',   { layer: 0, x: 11, y: 1, w: 1, h: 1 } ],
+    [ 'wolf',        { layer: 0, x: 0, y: 2, w: 1, h: 1 } ],
+    [ 'scorpion',    { layer: 0, x: 1, y: 2, w: 1, h: 1 } ],
+    [ 'polar_bear',  { layer: 0, x: 2, y: 2, w: 1, h: 1 } ],
+    [ 'crab',        { layer: 0, x: 3, y: 2, w: 1, h: 1 } ],
+]);
+
+const DROPPED_ITEM_SPRITE_MAP: Map<string, { layer: number, x: number, y: number }> = new Map([
+    [ 'wood',   { layer: 0, x: 9, y: 1 } ],
+    [ 'stone',  { layer: 0, x: 7, y: 1 } ],
+    [ 'fiber',  { layer: 0, x: 10, y: 1 } ],
+    [ 'cactus', { layer: 0, x: 3, y: 1 } ],
+    [ 'ice',    { layer: 0, x: 5, y: 1 } ],
+    [ 'shell',  { layer: 0, x: 11, y: 1 } ],
+    [ 'berries', { layer: 0, x: 6, y: 1 } ],
+    [ 'clam',   { layer: 0, x: 11, y: 1 } ],
+    [ 'meat',   { layer: 0, x: 4, y: 2 } ],
 ]);
 
 interface SpriteInstance {
@@ -159,6 +175,12 @@ export class EntityShader extends Shader {
                     4 * entity.skin + 1 + entity.animation_frame
                 );
             }
+            case 'dropped_item': {
+                const itemSprite = DROPPED_ITEM_SPRITE_MAP.get(entity.item);
+                if (!itemSprite) return;
+
+                return this.dispatchSpriteRender(x, y - 0.5, itemSprite.layer, itemSprite.x, itemSprite.y);
+            }
             default: throw new Error(`Do not know how to render entity of type "${entity_type}"`);
         }
     }
@@ -33,25 +33,105 @@ export function terrainTileTypeToNumber(ttt: TerrainTileType): number {
     }[ttt];
 }
 
+export function terrainTileTypeFromNumber(n: number): TerrainTileType {
+    const types: TerrainTileType[] = [
+        "DeepWater",
+        "Water",
+        "Beach",
+        "Grass",
+        "Forest",
+        "Desert",
+        "Savanna",
+        "Tundra",
+        "Snow",
+        "Stone",
+        "Jungle",
+        "Swamp",
+        "Ice",
+    ];
+
+    return types[n] ?? "DeepWater";
+}
+
 export interface TerrainChunk {
     position: Vec2,
     contents: TerrainTileType[],
 }
 
+export interface GameSettings {
+    world_size: number,
+    water_edge_size: number,
+    entity_spawn_rate: number,
+    pvp: boolean,
+    max_players: number,
+    max_player_health: number,
+    max_world_entity_health: number,
+    view_range: number,
+}
+
 export type Item =
+    | "wooden_sword"
+    | "wooden_pickaxe"
+    | "wooden_axe"
+    | "stone_sword"
+    | "stone_pickaxe"
+    | "stone_axe"
     | "iron_sword"
     | "iron_pickaxe"
-    | "iron_axe";
+    | "iron_axe"
+    | "gold_sword"
+    | "gold_pickaxe"
+    | "gold_axe"
+    | "diamond_sword"
+    | "diamond_pickaxe"
+    | "diamond_axe"
+    | "wood"
+    | "stone"
+    | "fiber"
+    | "cactus"
+    | "ice"
+    | "shell"
+    | "iron_ore"
+    | "gold_ore"
+    | "diamond"
+    | "berries"
+    | "clam"
+    | "meat";
 
-export function itemToNumber(item: Item): number {
+export function itemToNumber(item: Item): number | null {
     return {
         "iron_sword": 0,
         "iron_pickaxe": 1,
         "iron_axe": 2,
-    }[item]!;
+    }[item as string] ?? null;
+}
+
+export interface ItemStack {
+    item: Item,
+    count: number,
+    durability?: number,
+}
+
+const TOOL_MAX_DURABILITY: Record<string, number> = {
+    wooden: 60,
+    stone: 130,
+    iron: 250,
+    gold: 40,
+    diamond: 1500,
+};
+
+export function itemMaxDurability(item: Item): number | null {
+    return TOOL_MAX_DURABILITY[item.split('_')[0]] ?? null;
+}
+
+export interface Recipe {
+    name: string,
+    inputs: ItemStack[],
+    outputs: ItemStack[],
+    craftable: boolean,
 }
 
 export interface Inventory {
-    slots: [Item | null],
+    slots: (ItemStack | null)[],
     selected: number,
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 import type { Vec2 } from '@core/Vec2';
 import type { EntityType } from '@core/Entity';
-import type { TerrainChunk } from '@type/game';
+import type { GameSettings, ItemStack, Recipe, TerrainChunk } from '@type/game';
 
 export interface EntityMovePacket {
     packet_type: 'entity_move'
@@ -8,9 +8,22 @@ export interface EntityMovePacket {
     new_position: Vec2,
 }
 
-export interface TerrainChunkPacket {
-    packet_type: 'terrain_chunk',
-    chunk: TerrainChunk,
+export interface EntityMoveBatchPacket {
+    packet_type: 'entity_move_batch',
+    moves: {
+        id: string,
+        new_position: Vec2,
+    }[],
+}
+
+export interface TerrainChunkBatchPacket {
+    packet_type: 'terrain_chunk_batch',
+    chunks: TerrainChunk[],
+}
+
+export interface TerrainChunkUnloadPacket {
+    packet_type: 'terrain_chunk_unload',
+    chunks: Vec2[],
 }
 
 export interface EntityLoadPacket {
@@ -26,13 +39,21 @@ export interface EntityUnloadPacket {
 export interface PlayerRegisterPacket {
     packet_type: 'player_register';
     game_name: string;
-    username: string;
+    token: string;
     skin: number;
+    fresh_start?: boolean;
 }
 
 export interface PlayerRegisteredPacket {
     packet_type: 'player_registered',
     id: string,
+    resume_token: string,
+    settings: GameSettings,
+}
+
+export interface PlayerResumePacket {
+    packet_type: 'player_resume',
+    resume_token: string,
 }
 
 export interface ChatMessageSendPacket {
@@ -73,17 +94,96 @@ export interface InventorySelectPacket {
     selected: number,
 }
 
+export interface InventoryStatePacket {
+    packet_type: 'inventory_state',
+    slots: (ItemStack | null)[],
+    selected: number,
+}
+
+export interface ItemPickupPacket {
+    packet_type: 'item_pickup',
+    id: string,
+}
+
+export interface InventoryMovePacket {
+    packet_type: 'inventory_move',
+    from: number,
+    to: number,
+}
+
+export interface InventorySplitPacket {
+    packet_type: 'inventory_split',
+    from: number,
+    to: number,
+    count: number,
+}
+
+export interface CraftPacket {
+    packet_type: 'craft',
+    recipe: string,
+}
+
+export interface RecipeListRequestPacket {
+    packet_type: 'recipe_list_request',
+}
+
+export interface RecipeListPacket {
+    packet_type: 'recipe_list',
+    recipes: Recipe[],
+}
+
+export interface PlayerDeathPacket {
+    packet_type: 'player_death',
+    killer: string | null,
+    respawn_cooldown: number,
+    can_spectate: boolean,
+}
+
+export interface PlayerRespawnPacket {
+    packet_type: 'player_respawn',
+}
+
+export interface PlayerRespawnedPacket {
+    packet_type: 'player_respawned',
+}
+
+export interface UseItemPacket {
+    packet_type: 'use_item',
+    slot: number,
+}
+
+export interface PlayerStatsPacket {
+    packet_type: 'player_stats',
+    health: number,
+    hunger: number,
+}
+
 export type Packet =
     EntityMovePacket |
+    EntityMoveBatchPacket |
     EntityLoadPacket |
     EntityUnloadPacket |
-    TerrainChunkPacket |
+    TerrainChunkBatchPacket |
+    TerrainChunkUnloadPacket |
     PlayerRegisterPacket |
     PlayerRegisteredPacket |
+    PlayerResumePacket |
     ChatMessagePacket |
     ChatMessageSendPacket |
     SystemMessagePacket |
     PlayerAttackPacket |
     EntityDamagePacket |
     EntityDeathPacket |
-    InventorySelectPacket;
+    InventorySelectPacket |
+    InventoryStatePacket |
+    ItemPickupPacket |
+    InventoryMovePacket |
+    InventorySplitPacket |
+    CraftPacket |
+    RecipeListRequestPacket |
+    RecipeListPacket |
+    PlayerDeathPacket |
+    PlayerRespawnPacket |
+    PlayerRespawnedPacket |
+    UseItemPacket |
+    PlayerStatsPacket;
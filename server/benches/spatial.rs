@@ -0,0 +1,126 @@
+//! Compares the lookups `Game` does on its entities against scanning all of them, which is
+//! what they did before `EntityMap` bucketed entities by chunk.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use uuid::Uuid;
+
+use hexhavoc_server::config::{ATTACK_RANGE, CHUNK_SIZE, ENTITY_SIZE};
+use hexhavoc_server::model::{Game, Vec2};
+use hexhavoc_server::settings::GameSettings;
+use hexhavoc_server::spatial::{ChunkCoords, get_chunk_coords_of};
+
+const WORLD_SIZES: [i32; 3] = [256, 512, 1024];
+
+fn game(world_size: i32) -> Game {
+    let settings = GameSettings {
+        world_size,
+        water_edge_size: world_size / 8,
+        ..GameSettings::default()
+    };
+
+    Game::new(Uuid::new_v4(), "bench".to_string(), 1, settings)
+}
+
+fn center(game: &Game) -> Vec2 {
+    let middle = game.settings.world_size as f32 / 2.0;
+    Vec2::new(middle, middle)
+}
+
+/// The chunks a player in the middle of the world has loaded.
+fn visible_chunks(game: &Game) -> Vec<ChunkCoords> {
+    let (x, y) = get_chunk_coords_of(center(game));
+    let range = (game.settings.view_range / CHUNK_SIZE as f32).ceil() as i32;
+
+    (y - range..=y + range)
+        .flat_map(|cy| (x - range..=x + range).map(move |cx| (cx, cy)))
+        .collect()
+}
+
+fn visibility(c: &mut Criterion) {
+    let mut group = c.benchmark_group("visibility");
+
+    for world_size in WORLD_SIZES {
+        let game = game(world_size);
+        let chunks = visible_chunks(&game);
+
+        group.bench_function(BenchmarkId::new("linear", world_size), |b| {
+            b.iter(|| {
+                game.entity_map
+                    .values()
+                    .filter(|entity| chunks.contains(&get_chunk_coords_of(entity.position)))
+                    .count()
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("indexed", world_size), |b| {
+            b.iter(|| game.entity_map.in_chunks(black_box(&chunks)).count())
+        });
+    }
+
+    group.finish();
+}
+
+fn hit_test(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hit_test");
+
+    for world_size in WORLD_SIZES {
+        let game = game(world_size);
+        let cursor = center(&game);
+
+        group.bench_function(BenchmarkId::new("linear", world_size), |b| {
+            b.iter(|| {
+                game.entity_map
+                    .values()
+                    .filter(|entity| entity.value.is_attackable())
+                    .filter(|entity| entity.position.distance(black_box(cursor)) < ATTACK_RANGE)
+                    .count()
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("indexed", world_size), |b| {
+            b.iter(|| {
+                game.entity_map
+                    .near(black_box(cursor), ATTACK_RANGE)
+                    .filter(|entity| entity.value.is_attackable())
+                    .filter(|entity| entity.position.distance(cursor) < ATTACK_RANGE)
+                    .count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+
+    for world_size in WORLD_SIZES {
+        let game = game(world_size);
+        let position = center(&game);
+        let overlaps = |other: Vec2| {
+            (position.x - other.x).abs() < ENTITY_SIZE && (position.y - other.y).abs() < ENTITY_SIZE
+        };
+
+        group.bench_function(BenchmarkId::new("linear", world_size), |b| {
+            b.iter(|| {
+                game.entity_map
+                    .values()
+                    .any(|entity| entity.value.is_solid() && overlaps(black_box(entity.position)))
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("indexed", world_size), |b| {
+            b.iter(|| {
+                game.entity_map
+                    .near(black_box(position), ENTITY_SIZE)
+                    .any(|entity| entity.value.is_solid() && overlaps(entity.position))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, visibility, hit_test, collision);
+criterion_main!(benches);
@@ -0,0 +1,275 @@
+//! Plays many games at once against an in-process server to check that games do not stall each
+//! other. Every simulated player joins like a socket would, walks around at the client's send
+//! rate and regularly asks for its recipe list to measure how long its game takes to answer.
+//!
+//! Sockets are left out, packets go straight from `Client::recv` into the games and come back
+//! through the players' outboxes.
+
+use actix_web::rt;
+use clap::Parser;
+use rand::Rng;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use hexhavoc_server::model::{Client, Game, ServerState, SharedState, Vec2};
+use hexhavoc_server::outbox::{Outbox, OutboxReceiver, OutboxStats, Outgoing};
+use hexhavoc_server::packet::Packet;
+use hexhavoc_server::settings::GameSettings;
+
+/// Rate at which the browser client sends its position.
+const MOVE_RATE: u32 = 20;
+
+#[derive(Parser)]
+struct Args {
+    /// Number of games played at once
+    #[arg(long, default_value_t = 50)]
+    games: usize,
+
+    /// Number of players in every game
+    #[arg(long, default_value_t = 8)]
+    players: usize,
+
+    /// How long the players play
+    #[arg(long, default_value_t = 10)]
+    seconds: u64,
+
+    /// Number of players in every game that stop reading their packets after joining
+    #[arg(long, default_value_t = 0)]
+    stalled: usize,
+}
+
+#[derive(Default)]
+struct PlayerReport {
+    packets: usize,
+    latencies: Vec<Duration>,
+    peak_queue: usize,
+}
+
+#[actix_web::main]
+async fn main() {
+    let args = Args::parse();
+    let state: SharedState = Arc::new(Mutex::new(ServerState::default()));
+
+    let mut tokens = Vec::new();
+
+    {
+        let mut state_guard = state.lock().await;
+
+        for game in 0..args.games {
+            let id = state_guard.add_game(Game::new(
+                Uuid::new_v4(),
+                format!("load-{game}"),
+                game as u32,
+                GameSettings::default(),
+            ));
+            state_guard.start_game_loop(id, state.clone());
+
+            for player in 0..args.players + args.stalled {
+                // Sessions do not need a stored account
+                let token = state_guard
+                    .accounts
+                    .create_session(&format!("g{game}p{player}"));
+                tokens.push((game, token, player >= args.players));
+            }
+        }
+    }
+
+    println!(
+        "{} games with {} players and {} stalled players each for {} seconds",
+        args.games, args.players, args.stalled, args.seconds
+    );
+
+    let started = Instant::now();
+    let duration = Duration::from_secs(args.seconds);
+
+    let handles: Vec<_> = tokens
+        .into_iter()
+        .map(|(game, token, stalled)| {
+            let game_name = format!("load-{game}");
+            rt::spawn(play(state.clone(), game_name, token, stalled, duration))
+        })
+        .collect();
+
+    let mut packets = 0;
+    let mut latencies = Vec::new();
+    let mut peak_queue = 0;
+
+    for handle in handles {
+        let report = handle.await.expect("players do not panic");
+        packets += report.packets;
+        latencies.extend(report.latencies);
+        peak_queue = peak_queue.max(report.peak_queue);
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    let stats = {
+        let state = state.lock().await;
+        state
+            .outbox_metrics
+            .stats(state.clients.values().map(|client| &client.outbox))
+    };
+    latencies.sort();
+
+    let percentile = |p: f64| {
+        latencies
+            .get(((latencies.len() as f64 * p) as usize).min(latencies.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    println!(
+        "{} packets sent to players, {:.0} per second",
+        packets,
+        packets as f64 / elapsed
+    );
+    println!(
+        "Recipe list round trips: {} answered, p50 {:?}, p99 {:?}, max {:?}",
+        latencies.len(),
+        percentile(0.5),
+        percentile(0.99),
+        latencies.last().copied().unwrap_or_default()
+    );
+    println!(
+        "Outboxes: deepest queue {}, {} moves coalesced, {} clients dropped for not keeping up",
+        peak_queue, stats.coalesced_moves, stats.evicted_clients
+    );
+}
+
+/// Joins a game and walks back and forth until `duration` is over.
+async fn play(
+    state: SharedState,
+    game_name: String,
+    token: String,
+    stalled: bool,
+    duration: Duration,
+) -> PlayerReport {
+    let (mut client, mut outgoing) = {
+        let mut state = state.lock().await;
+
+        let (outbox, outgoing) = Outbox::channel(state.outbox_metrics.clone());
+        let client = Client::new(Uuid::new_v4(), outbox);
+        state.clients.insert(client.id, client.clone());
+
+        (client, outgoing)
+    };
+    let mut report = PlayerReport::default();
+
+    client
+        .recv(
+            Packet::PlayerRegister {
+                game_name,
+                token,
+                skin: 0,
+                fresh_start: true,
+            },
+            &state,
+        )
+        .await;
+
+    let Some(mut position) = spawn_position(client.id, &mut outgoing, &mut report).await else {
+        return report;
+    };
+
+    if stalled {
+        // Keeps the receiver so the queue fills up instead of being closed
+        rt::time::sleep(duration).await;
+        report.peak_queue = queue_stats(&state, &client).await.peak_queue;
+        drop(outgoing);
+        return report;
+    }
+
+    let mut moves = rt::time::interval(Duration::from_secs_f64(1.0 / MOVE_RATE as f64));
+    let mut requests = rt::time::interval(Duration::from_secs(1));
+    let mut request_sent: Option<Instant> = None;
+    let mut step = 0.1;
+
+    let end = rt::time::sleep(duration);
+    tokio::pin!(end);
+
+    loop {
+        tokio::select! {
+            _ = &mut end => break,
+
+            _ = moves.tick() => {
+                // Walks a few tiles back and forth so views change without leaving the area
+                if rand::rng().random_bool(0.05) {
+                    step = -step;
+                }
+
+                let new_position = Vec2::new(position.x + step, position.y);
+                client.recv(Packet::EntityMove { id: client.id, new_position }, &state).await;
+                position = new_position;
+            }
+
+            _ = requests.tick() => {
+                if request_sent.is_none() {
+                    request_sent = Some(Instant::now());
+                    client.recv(Packet::RecipeListRequest, &state).await;
+                }
+            }
+
+            Some(outgoing) = outgoing.recv() => {
+                let Outgoing::Packet(packet) = outgoing else {
+                    continue;
+                };
+
+                report.packets += 1;
+
+                match packet {
+                    // Rejected moves put the player back
+                    Packet::EntityMove { id, new_position } if id == client.id => {
+                        position = new_position;
+                    }
+                    Packet::RecipeList { .. } => {
+                        if let Some(sent) = request_sent.take() {
+                            report.latencies.push(sent.elapsed());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    report.peak_queue = queue_stats(&state, &client).await.peak_queue;
+
+    state
+        .lock()
+        .await
+        .disconnect_client(client.id, &client.outbox)
+        .await;
+
+    report
+}
+
+/// Waits for the player's own entity to be loaded after joining.
+async fn spawn_position(
+    id: Uuid,
+    outgoing: &mut OutboxReceiver,
+    report: &mut PlayerReport,
+) -> Option<Vec2> {
+    while let Some(outgoing) = outgoing.recv().await {
+        match outgoing {
+            Outgoing::Packet(Packet::EntityLoad { entity }) if entity.id == id => {
+                report.packets += 1;
+                return Some(entity.position);
+            }
+            Outgoing::Packet(_) => report.packets += 1,
+            Outgoing::Error(error) => {
+                eprintln!("Could not join: {}", error.error);
+                return None;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+async fn queue_stats(state: &SharedState, client: &Client) -> OutboxStats {
+    state.lock().await.outbox_metrics.stats([&client.outbox])
+}
@@ -0,0 +1,176 @@
+use actix_web::web;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::*;
+use crate::model::SharedState;
+
+#[derive(Debug)]
+pub enum AccountError {
+    InvalidUsername,
+    PasswordTooShort,
+    UsernameTaken,
+    WrongCredentials,
+    Storage(io::Error),
+}
+
+impl AccountError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccountError::InvalidUsername => "invalid-username",
+            AccountError::PasswordTooShort => "password-too-short",
+            AccountError::UsernameTaken => "username-taken",
+            AccountError::WrongCredentials => "wrong-credentials",
+            AccountError::Storage(_) => "storage-error",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Account {
+    password_hash: String,
+}
+
+/// Accounts are kept in a JSON file keyed by username. Session tokens only live in memory,
+/// so everyone has to log in again after a restart.
+#[derive(Default)]
+pub struct AccountStore {
+    path: PathBuf,
+    accounts: HashMap<String, Account>,
+    sessions: HashMap<String, String>,
+    /// Held while writing the file, so writes never overlap
+    write_lock: Arc<Mutex<()>>,
+}
+
+/// Hashing is slow on purpose, so callers should run it off the async executor.
+pub fn hash_password(password: &str) -> String {
+    let salt_bytes: [u8; 16] = rand::rng().random();
+    let salt = SaltString::encode_b64(&salt_bytes).expect("16 bytes are a valid salt");
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("argon2 with default params accepts any password")
+        .to_string()
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+impl AccountStore {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let accounts = match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path,
+            accounts,
+            sessions: HashMap::new(),
+            write_lock: Arc::default(),
+        })
+    }
+
+    pub fn validate(username: &str, password: &str) -> Result<(), AccountError> {
+        let valid_username = (1..=MAX_USERNAME_LENGTH).contains(&username.chars().count())
+            && username
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+        if !valid_username {
+            return Err(AccountError::InvalidUsername);
+        }
+
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AccountError::PasswordTooShort);
+        }
+
+        Ok(())
+    }
+
+    pub fn exists(&self, username: &str) -> bool {
+        self.accounts.contains_key(username)
+    }
+
+    pub fn password_hash(&self, username: &str) -> Option<String> {
+        self.accounts
+            .get(username)
+            .map(|account| account.password_hash.clone())
+    }
+
+    /// Adds an account in memory, it has to be written with `save_accounts` afterwards.
+    pub fn add(&mut self, username: &str, password_hash: String) -> Result<(), AccountError> {
+        if self.exists(username) {
+            return Err(AccountError::UsernameTaken);
+        }
+
+        self.accounts
+            .insert(username.to_string(), Account { password_hash });
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, username: &str) {
+        self.accounts.remove(username);
+    }
+
+    pub fn create_session(&mut self, username: &str) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        self.sessions.insert(token.clone(), username.to_string());
+
+        token
+    }
+
+    pub fn username_for_token(&self, token: &str) -> Option<&str> {
+        self.sessions.get(token).map(String::as_str)
+    }
+}
+
+fn write_accounts(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let tmp_path = path.with_extension("json.tmp");
+
+    fs::write(&tmp_path, data)?;
+    fs::rename(tmp_path, path)
+}
+
+/// Writes the accounts to disk off the async executor. The server state is only locked while
+/// serializing them, and each write takes the accounts as they are once the previous write is
+/// done, so an older copy never replaces a newer one.
+pub async fn save_accounts(state: &SharedState) -> io::Result<()> {
+    let write_lock = state.lock().await.accounts.write_lock.clone();
+    let _writing = write_lock.lock().await;
+
+    let (path, data) = {
+        let state = state.lock().await;
+        let accounts = &state.accounts;
+        (
+            accounts.path.clone(),
+            serde_json::to_vec(&accounts.accounts)?,
+        )
+    };
+
+    web::block(move || write_accounts(&path, &data))
+        .await
+        .map_err(io::Error::other)?
+}
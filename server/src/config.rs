@@ -1,11 +1,237 @@
-pub const VIEW_RANGE: f32 = 32.0;
+use clap::Parser;
+use log::LevelFilter;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::settings::GameSettings;
 
-pub const WORLD_SIZE: i32 = 256;
 pub const CHUNK_SIZE: i32 = 8;
-pub const WATER_EDGE_SIZE: i32 = 32;
-pub const WORLD_ENTITY_SPAWN_RATE: f64 = 0.4;
 
-pub const MAX_PLAYER_HEALTH: i32 = 250;
-pub const MAX_WORLD_ENTITY_HEALTH: i32 = 100;
+// Defaults of the server options
+pub const DEFAULT_CONFIG_FILE: &str = "hexhavoc.toml";
+pub const DEFAULT_ADDRESS: &str = "0.0.0.0";
+pub const DEFAULT_PORT: u16 = 9872;
+pub const DEFAULT_STATIC_DIR: &str = "client/dist";
+pub const DEFAULT_SAVE_DIR: &str = "saves";
+pub const DEFAULT_ACCOUNTS_FILE: &str = "accounts.json";
+pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+pub const DEFAULT_GAME_IDLE_TIMEOUT_SECONDS: u64 = 60 * 60;
+
+// Defaults of the per-game settings
+pub const DEFAULT_VIEW_RANGE: f32 = 32.0;
+
+pub const DEFAULT_WORLD_SIZE: i32 = 256;
+pub const DEFAULT_WATER_EDGE_SIZE: i32 = 32;
+pub const DEFAULT_WORLD_ENTITY_SPAWN_RATE: f64 = 0.4;
+
+pub const DEFAULT_MAX_PLAYER_HEALTH: i32 = 250;
+pub const DEFAULT_MAX_WORLD_ENTITY_HEALTH: i32 = 100;
+
+pub const DEFAULT_PVP: bool = true;
+pub const DEFAULT_MAX_PLAYERS: usize = 32;
 
 pub const ATTACK_RANGE: f32 = 1.5;
+pub const PICKUP_RANGE: f32 = 2.0;
+
+pub const ENTITY_SIZE: f32 = 0.75;
+
+pub const MAX_PLAYER_SPEED: f32 = 8.0;
+pub const MOVE_SPEED_TOLERANCE: f32 = 1.25;
+pub const MAX_MOVE_BURST: f32 = 0.5;
+
+pub const SPAWN_LOCATION_ATTEMPTS: u32 = 1000;
+/// Side length of the area players spawn in, inside the water border
+pub const MIN_SPAWN_AREA_SIZE: i32 = 4 * CHUNK_SIZE;
+
+pub const RESPAWN_COOLDOWN: f32 = 5.0;
+pub const ALLOW_SPECTATING: bool = true;
+
+pub const DISCONNECT_GRACE_PERIOD: f32 = 30.0;
+pub const CLIENT_TIMEOUT_SECONDS: u64 = 45;
+
+pub const OUTBOX_FULL_LENGTH: usize = 1024;
+pub const OUTBOX_FULL_TIMEOUT_SECONDS: f32 = 5.0;
+pub const OUTBOX_CAPACITY: usize = 4096;
+
+/// Packets a game can have waiting for its loop before the client sending the next one is dropped
+pub const GAME_INPUT_CAPACITY: usize = 1024;
+
+pub const TICK_RATE: u32 = 20;
+
+pub const MAX_HUNGER: i32 = 100;
+pub const HUNGER_DECAY_SECONDS: u64 = 5;
+pub const REGEN_MIN_HUNGER: i32 = 50;
+pub const HEALTH_REGEN_PER_SECOND: i32 = 2;
+pub const STARVATION_DAMAGE_PER_SECOND: i32 = 5;
+
+pub const MAX_MOBS: usize = 40;
+pub const MOB_SPAWN_ATTEMPTS: u32 = 5;
+pub const MOB_SPAWN_MIN_PLAYER_DISTANCE: f32 = 16.0;
+pub const MOB_ATTACK_COOLDOWN: f32 = 1.0;
+
+pub const SAVE_INTERVAL_SECONDS: u64 = 60;
+
+pub const MAX_USERNAME_LENGTH: usize = 16;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Options of a server instance. Values from the config file are overridden by environment
+/// variables, which are overridden by command-line flags.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    pub static_dir: PathBuf,
+    pub save_dir: PathBuf,
+    pub accounts_file: PathBuf,
+    /// JSON file with the crafting recipes, the built-in ones are used without it
+    pub recipes_file: Option<PathBuf>,
+    pub log_level: LevelFilter,
+    /// Games without players are saved and unloaded after this long, `0` keeps them loaded
+    pub game_idle_timeout_seconds: u64,
+    /// Settings of games created without choosing them
+    pub world: GameSettings,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS.to_string(),
+            port: DEFAULT_PORT,
+            static_dir: DEFAULT_STATIC_DIR.into(),
+            save_dir: DEFAULT_SAVE_DIR.into(),
+            accounts_file: DEFAULT_ACCOUNTS_FILE.into(),
+            recipes_file: None,
+            log_level: DEFAULT_LOG_LEVEL,
+            game_idle_timeout_seconds: DEFAULT_GAME_IDLE_TIMEOUT_SECONDS,
+            world: GameSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(version, about = "HexHavoc game server")]
+pub struct Args {
+    /// TOML config file, `hexhavoc.toml` is read if it exists
+    #[arg(short, long, env = "HEXHAVOC_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Address to listen on
+    #[arg(long, env = "HEXHAVOC_ADDRESS")]
+    address: Option<String>,
+
+    /// Port to listen on
+    #[arg(short, long, env = "HEXHAVOC_PORT")]
+    port: Option<u16>,
+
+    /// Directory of the built client
+    #[arg(long, env = "HEXHAVOC_STATIC_DIR")]
+    static_dir: Option<PathBuf>,
+
+    /// Directory games are saved to
+    #[arg(long, env = "HEXHAVOC_SAVE_DIR")]
+    save_dir: Option<PathBuf>,
+
+    /// File accounts are stored in
+    #[arg(long, env = "HEXHAVOC_ACCOUNTS_FILE")]
+    accounts_file: Option<PathBuf>,
+
+    /// JSON file with the crafting recipes, replacing the built-in ones
+    #[arg(long, env = "HEXHAVOC_RECIPES_FILE")]
+    recipes_file: Option<PathBuf>,
+
+    /// One of off, error, warn, info, debug and trace
+    #[arg(long, env = "HEXHAVOC_LOG_LEVEL")]
+    log_level: Option<LevelFilter>,
+
+    /// Seconds after which games without players are saved and unloaded, 0 keeps them loaded
+    #[arg(long, env = "HEXHAVOC_GAME_IDLE_TIMEOUT_SECONDS")]
+    game_idle_timeout_seconds: Option<u64>,
+
+    #[command(flatten)]
+    world: WorldArgs,
+}
+
+#[derive(Debug, clap::Args)]
+#[command(next_help_heading = "Default game settings")]
+struct WorldArgs {
+    #[arg(long, env = "HEXHAVOC_WORLD_SIZE")]
+    world_size: Option<i32>,
+
+    #[arg(long, env = "HEXHAVOC_WATER_EDGE_SIZE")]
+    water_edge_size: Option<i32>,
+
+    #[arg(long, env = "HEXHAVOC_ENTITY_SPAWN_RATE")]
+    entity_spawn_rate: Option<f64>,
+
+    #[arg(long, env = "HEXHAVOC_PVP")]
+    pvp: Option<bool>,
+
+    #[arg(long, env = "HEXHAVOC_MAX_PLAYERS")]
+    max_players: Option<usize>,
+
+    #[arg(long, env = "HEXHAVOC_MAX_PLAYER_HEALTH")]
+    max_player_health: Option<i32>,
+
+    #[arg(long, env = "HEXHAVOC_MAX_WORLD_ENTITY_HEALTH")]
+    max_world_entity_health: Option<i32>,
+
+    #[arg(long, env = "HEXHAVOC_VIEW_RANGE")]
+    view_range: Option<f32>,
+}
+
+impl ServerConfig {
+    pub fn load(args: Args) -> Result<Self, String> {
+        let mut config = match &args.config {
+            Some(path) => Self::read(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::read(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+
+        config.address = args.address.unwrap_or(config.address);
+        config.port = args.port.unwrap_or(config.port);
+        config.static_dir = args.static_dir.unwrap_or(config.static_dir);
+        config.save_dir = args.save_dir.unwrap_or(config.save_dir);
+        config.accounts_file = args.accounts_file.unwrap_or(config.accounts_file);
+        config.recipes_file = args.recipes_file.or(config.recipes_file);
+        config.log_level = args.log_level.unwrap_or(config.log_level);
+        config.game_idle_timeout_seconds = args
+            .game_idle_timeout_seconds
+            .unwrap_or(config.game_idle_timeout_seconds);
+
+        let (world, defaults) = (&mut config.world, args.world);
+        world.world_size = defaults.world_size.unwrap_or(world.world_size);
+        world.water_edge_size = defaults.water_edge_size.unwrap_or(world.water_edge_size);
+        world.entity_spawn_rate = defaults
+            .entity_spawn_rate
+            .unwrap_or(world.entity_spawn_rate);
+        world.pvp = defaults.pvp.unwrap_or(world.pvp);
+        world.max_players = defaults.max_players.unwrap_or(world.max_players);
+        world.max_player_health = defaults
+            .max_player_health
+            .unwrap_or(world.max_player_health);
+        world.max_world_entity_health = defaults
+            .max_world_entity_health
+            .unwrap_or(world.max_world_entity_health);
+        world.view_range = defaults.view_range.unwrap_or(world.view_range);
+
+        config
+            .world
+            .validate()
+            .map_err(|setting| format!("Invalid world setting {setting}"))?;
+
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+
+        toml::from_str(&contents).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+use crate::inventory::{Inventory, ItemStack};
+
+const BUILTIN_RECIPES: &str = include_str!("recipes.json");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub inputs: Vec<ItemStack>,
+    pub outputs: Vec<ItemStack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvailableRecipe {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    pub craftable: bool,
+}
+
+#[derive(Debug)]
+pub enum CraftError {
+    UnknownRecipe,
+    MissingIngredients,
+    InventoryFull,
+}
+
+impl Recipe {
+    pub fn can_craft(&self, inventory: &Inventory) -> bool {
+        self.inputs
+            .iter()
+            .all(|input| inventory.count(input.item) >= input.count)
+    }
+
+    /// Consumes the inputs and adds the outputs. The inventory is left untouched if
+    /// anything is missing or the outputs do not fit.
+    pub fn craft(&self, inventory: &mut Inventory) -> Result<(), CraftError> {
+        let mut crafted = inventory.clone();
+
+        for input in &self.inputs {
+            if !crafted.remove(input.item, input.count) {
+                return Err(CraftError::MissingIngredients);
+            }
+        }
+
+        for output in &self.outputs {
+            if crafted.add(*output) > 0 {
+                return Err(CraftError::InventoryFull);
+            }
+        }
+
+        *inventory = crafted;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecipeRegistry {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeRegistry {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(Self {
+            recipes: serde_json::from_str(json)?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+
+        Self::from_json(&contents).map_err(|e| format!("Invalid recipes {}: {}", path.display(), e))
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.name == name)
+    }
+
+    pub fn craft(&self, name: &str, inventory: &mut Inventory) -> Result<(), CraftError> {
+        self.get(name)
+            .ok_or(CraftError::UnknownRecipe)?
+            .craft(inventory)
+    }
+}
+
+impl Default for RecipeRegistry {
+    fn default() -> Self {
+        Self::from_json(BUILTIN_RECIPES).expect("Built-in recipes are invalid")
+    }
+}
@@ -0,0 +1,97 @@
+use actix_web::{HttpResponse, Responder, post, web};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::{AccountError, AccountStore, hash_password, save_accounts, verify_password};
+use crate::model::SharedState;
+use crate::packet::ErrorPacket;
+
+#[derive(Deserialize)]
+struct AccountForm {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct SessionResponse {
+    token: String,
+}
+
+fn error_response(error: AccountError) -> HttpResponse {
+    let mut response = match error {
+        AccountError::InvalidUsername | AccountError::PasswordTooShort => {
+            HttpResponse::BadRequest()
+        }
+        AccountError::UsernameTaken => HttpResponse::Conflict(),
+        AccountError::WrongCredentials => HttpResponse::Unauthorized(),
+        AccountError::Storage(ref e) => {
+            error!("[ACCOUNTS] Could not store accounts: {}", e);
+            HttpResponse::InternalServerError()
+        }
+    };
+
+    response.json(ErrorPacket::new(error.code()))
+}
+
+#[post("/register")]
+async fn register(
+    web::Form(form): web::Form<AccountForm>,
+    shared_state: web::Data<SharedState>,
+) -> impl Responder {
+    let username = form.username.trim().to_string();
+
+    if let Err(e) = AccountStore::validate(&username, &form.password) {
+        return error_response(e);
+    }
+
+    if shared_state.lock().await.accounts.exists(&username) {
+        return error_response(AccountError::UsernameTaken);
+    }
+
+    let Ok(password_hash) = web::block(move || hash_password(&form.password)).await else {
+        return HttpResponse::InternalServerError().finish();
+    };
+
+    if let Err(e) = shared_state
+        .lock()
+        .await
+        .accounts
+        .add(&username, password_hash)
+    {
+        return error_response(e);
+    }
+
+    // The disk is written without holding the server state
+    if let Err(e) = save_accounts(&shared_state).await {
+        shared_state.lock().await.accounts.remove(&username);
+        return error_response(AccountError::Storage(e));
+    }
+
+    info!("[ACCOUNTS] Registered {}", username);
+
+    HttpResponse::Ok().json(SessionResponse {
+        token: shared_state.lock().await.accounts.create_session(&username),
+    })
+}
+
+#[post("/login")]
+async fn login(
+    web::Form(form): web::Form<AccountForm>,
+    shared_state: web::Data<SharedState>,
+) -> impl Responder {
+    let username = form.username.trim().to_string();
+
+    let Some(password_hash) = shared_state.lock().await.accounts.password_hash(&username) else {
+        return error_response(AccountError::WrongCredentials);
+    };
+
+    let verified = web::block(move || verify_password(&form.password, &password_hash)).await;
+
+    if !matches!(verified, Ok(true)) {
+        return error_response(AccountError::WrongCredentials);
+    }
+
+    HttpResponse::Ok().json(SessionResponse {
+        token: shared_state.lock().await.accounts.create_session(&username),
+    })
+}
@@ -2,6 +2,7 @@ use actix_files::NamedFile;
 use actix_web::{HttpRequest, HttpResponse, Responder, Result, get, web};
 
 use crate::model::SharedState;
+use crate::save;
 
 #[get("/game/{name}")]
 async fn game(
@@ -10,10 +11,11 @@ async fn game(
     state: web::Data<SharedState>,
 ) -> Result<impl Responder> {
     let name = name.into_inner();
-    let state = state.lock().await;
 
-    if state.game_ids_by_name.contains_key(&name) {
-        let file = NamedFile::open_async("./client/dist/game.html").await?;
+    // Games nobody played for a while are loaded again when someone opens them
+    if save::load_game(&state, &name).await {
+        let path = state.lock().await.config.static_dir.join("game.html");
+        let file = NamedFile::open_async(path).await?;
 
         Ok(file.into_response(&req))
     } else {
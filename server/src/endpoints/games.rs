@@ -0,0 +1,126 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, delete, get, http::header, web};
+use serde::Serialize;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+use crate::model::SharedState;
+use crate::packet::ErrorPacket;
+use crate::save;
+use crate::settings::GameSettings;
+
+#[derive(Serialize)]
+struct GameInfo {
+    id: Uuid,
+    name: String,
+    seed: u32,
+    owner: Option<String>,
+    players: usize,
+    age_seconds: u64,
+    settings: GameSettings,
+}
+
+fn age_seconds(created_at: SystemTime) -> u64 {
+    SystemTime::now()
+        .duration_since(created_at)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[get("/games")]
+async fn list_games(state: web::Data<SharedState>) -> impl Responder {
+    let (games, mut infos) = {
+        let state = state.lock().await;
+        let games: Vec<_> = state.games.values().cloned().collect();
+
+        let unloaded: Vec<_> = state
+            .unloaded_games
+            .values()
+            .map(|game| GameInfo {
+                id: game.id,
+                name: game.name.clone(),
+                seed: game.seed,
+                owner: game.owner.clone(),
+                players: 0,
+                age_seconds: age_seconds(game.created_at),
+                settings: game.settings.clone(),
+            })
+            .collect();
+
+        (games, unloaded)
+    };
+
+    for game_arc in games {
+        let game = game_arc.lock().await;
+
+        infos.push(GameInfo {
+            id: game.id,
+            name: game.name.clone(),
+            seed: game.seed,
+            owner: game.owner.clone(),
+            players: game.player_count(),
+            age_seconds: age_seconds(game.created_at),
+            settings: game.settings.clone(),
+        });
+    }
+
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+    HttpResponse::Ok().json(infos)
+}
+
+/// Only the account that created a game may delete it. The session token is passed as
+/// `Authorization: Bearer <token>`.
+#[delete("/games/{name}")]
+async fn delete_game(
+    req: HttpRequest,
+    name: web::Path<String>,
+    shared_state: web::Data<SharedState>,
+) -> impl Responder {
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    let (id, game_loop) = {
+        let mut state = shared_state.lock().await;
+
+        let Some(username) = token
+            .and_then(|token| state.accounts.username_for_token(token))
+            .map(String::from)
+        else {
+            return HttpResponse::Unauthorized().json(ErrorPacket::new("invalid-session"));
+        };
+
+        let loaded = state
+            .game_ids_by_name
+            .get(name.as_str())
+            .and_then(|id| state.games.get(id))
+            .cloned();
+
+        let (id, owner) = match (loaded, state.unloaded_games.get(name.as_str())) {
+            (Some(game_arc), _) => {
+                let game = game_arc.lock().await;
+                (game.id, game.owner.clone())
+            }
+            (None, Some(unloaded)) => (unloaded.id, unloaded.owner.clone()),
+            (None, None) => {
+                return HttpResponse::NotFound().json(ErrorPacket::new("game-not-found"));
+            }
+        };
+
+        if owner.as_ref() != Some(&username) {
+            return HttpResponse::Forbidden().json(ErrorPacket::new("not-game-owner"));
+        }
+
+        (id, state.delete_game(id).await)
+    };
+
+    if let Some(game_loop) = game_loop {
+        game_loop.shutdown().await;
+    }
+
+    save::delete_game_save(&shared_state, id).await;
+
+    HttpResponse::NoContent().finish()
+}
@@ -0,0 +1,12 @@
+use actix_web::{HttpResponse, Responder, get, web};
+
+use crate::model::SharedState;
+
+/// How far behind the connections are in receiving their packets.
+#[get("/metrics")]
+async fn metrics(state: web::Data<SharedState>) -> impl Responder {
+    let state = state.lock().await;
+    let clients = state.clients.values().map(|client| &client.outbox);
+
+    HttpResponse::Ok().json(state.outbox_metrics.stats(clients))
+}
@@ -1,29 +1,112 @@
-use actix_web::{Responder, post, web};
+use actix_web::{HttpResponse, post, web};
 use serde::Deserialize;
+use uuid::Uuid;
 
-use crate::model::SharedState;
+use crate::model::{Game, SharedState};
+use crate::settings::GameSettings;
 
 #[derive(Deserialize)]
 struct NewGameForm {
     name: String,
     seed: u32,
+    token: Option<String>,
+
+    // Settings left out of the form use the server's defaults
+    world_size: Option<i32>,
+    water_edge_size: Option<i32>,
+    entity_spawn_rate: Option<f64>,
+    pvp: Option<bool>,
+    max_players: Option<usize>,
+    max_player_health: Option<i32>,
+    max_world_entity_health: Option<i32>,
+    view_range: Option<f32>,
+}
+
+impl NewGameForm {
+    fn settings(&self, defaults: &GameSettings) -> GameSettings {
+        let world_size = self.world_size.unwrap_or(defaults.world_size);
+
+        GameSettings {
+            world_size,
+            // The water edge scales with the world unless given explicitly
+            water_edge_size: self
+                .water_edge_size
+                .unwrap_or(world_size * defaults.water_edge_size / defaults.world_size),
+            entity_spawn_rate: self.entity_spawn_rate.unwrap_or(defaults.entity_spawn_rate),
+            pvp: self.pvp.unwrap_or(defaults.pvp),
+            max_players: self.max_players.unwrap_or(defaults.max_players),
+            max_player_health: self.max_player_health.unwrap_or(defaults.max_player_health),
+            max_world_entity_health: self
+                .max_world_entity_health
+                .unwrap_or(defaults.max_world_entity_health),
+            view_range: self.view_range.unwrap_or(defaults.view_range),
+        }
+    }
+}
+
+fn redirect(location: String) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .append_header(("Location", location))
+        .finish()
+}
+
+fn game_found(name: &str, id: Uuid) -> HttpResponse {
+    redirect(format!("/?error=game-found&game_name={name}&id={id}"))
 }
 
 #[post("/new_game")]
 async fn create_new_game(
     web::Form(form): web::Form<NewGameForm>,
-    state: web::Data<SharedState>,
-) -> impl Responder {
-    let mut state = state.lock().await;
-
-    let name = &form.name;
+    shared_state: web::Data<SharedState>,
+) -> HttpResponse {
+    let name = form.name.clone();
     let seed = form.seed;
 
-    if let Some(id) = state.game_ids_by_name.get(name) {
-        web::Redirect::to(format!("/?error=game-found&game_name={name}&id={id}")).see_other()
-    } else {
-        let _id = state.create_game(name, seed);
+    let (settings, owner) = {
+        let state = shared_state.lock().await;
 
-        web::Redirect::to(format!("/game/{name}")).see_other()
+        let settings = form.settings(&state.config.world);
+        if let Err(setting) = settings.validate() {
+            return redirect(format!("/?error=invalid-setting&setting={setting}"));
+        }
+
+        if let Some(id) = state.game_id_named(&name) {
+            return game_found(&name, id);
+        }
+
+        // Games created while logged in can be deleted by their creator
+        let owner = form
+            .token
+            .as_deref()
+            .and_then(|token| state.accounts.username_for_token(token).map(String::from));
+
+        (settings, owner)
+    };
+
+    // Generating the world takes a while, so it is done before locking the state
+    let game = {
+        let name = name.clone();
+        web::block(move || {
+            let mut game = Game::new(Uuid::new_v4(), name, seed, settings);
+            game.owner = owner;
+            game
+        })
+        .await
+    };
+
+    let Ok(game) = game else {
+        return HttpResponse::InternalServerError().finish();
+    };
+
+    let mut state = shared_state.lock().await;
+
+    // Another request may have taken the name in the meantime
+    if let Some(id) = state.game_id_named(&name) {
+        return game_found(&name, id);
     }
+
+    let id = state.add_game(game);
+    state.start_game_loop(id, shared_state.get_ref().clone());
+
+    redirect(format!("/game/{name}"))
 }
@@ -1,83 +1,90 @@
 use actix_web::{Error, HttpRequest, HttpResponse, get, rt, web};
 use actix_ws::Message;
 use futures_util::StreamExt;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use serde::Deserialize;
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::config::CLIENT_TIMEOUT_SECONDS;
 use crate::model::{Client, SharedState};
-use crate::packet::Packet;
+use crate::outbox::{Outbox, write_outgoing};
+use crate::packet::{Encoding, Packet};
+
+#[derive(Deserialize)]
+struct WsQuery {
+    #[serde(default)]
+    encoding: Encoding,
+}
 
 #[get("/ws")]
 async fn ws(
     req: HttpRequest,
     stream: web::Payload,
+    query: web::Query<WsQuery>,
     state: web::Data<SharedState>,
 ) -> Result<HttpResponse, Error> {
     let (res, session, mut stream) = actix_ws::handle(&req, stream)?;
 
-    let id = Uuid::new_v4();
+    let mut client = {
+        let mut state = state.lock().await;
 
-    {
-        let client = Client {
-            id,
-            ws_session: Arc::new(Mutex::new(session)),
-            game: None,
-            username: None,
-        };
+        let (outbox, outgoing) = Outbox::channel(state.outbox_metrics.clone());
+        rt::spawn(write_outgoing(session, outgoing, query.encoding));
 
-        client.log("Connected").await;
+        let client = Client::new(Uuid::new_v4(), outbox);
+        state.clients.insert(client.id, client.clone());
 
-        let mut locked_state = state.lock().await;
-        locked_state.clients.insert(id, client);
-    }
+        client
+    };
+
+    client.log("Connected");
+    let outbox = client.outbox.clone();
 
     rt::spawn(async move {
-        while let Some(msg) = stream.next().await {
-            match msg {
+        let client_timeout = Duration::from_secs(CLIENT_TIMEOUT_SECONDS);
+
+        while let Ok(Some(msg)) = rt::time::timeout(client_timeout, stream.next()).await {
+            // Unparsable messages are kept in a printable form for the log
+            let packet = match msg {
                 Ok(Message::Text(text)) => {
-                    if let Ok(packet) = serde_json::from_str::<Packet>(&text) {
-                        let client_opt = {
-                            let locked_state = state.lock().await;
-                            locked_state.clients.get(&id).cloned()
-                        };
-
-                        if let Some(mut client) = client_opt {
-                            let mut locked_state = state.lock().await;
-                            client.recv(packet, &mut locked_state).await;
-                        } else {
-                            eprintln!("[WS] Missing client {}", id);
-                        }
-                    } else {
-                        let locked_state = state.lock().await;
-                        if let Some(client) = locked_state.clients.get(&id)
-                            && !text.is_empty()
-                        {
-                            client
-                                .elog(format!("Sent an unparsable packet: {}", text))
-                                .await;
-                        }
-                    }
+                    serde_json::from_str::<Packet>(&text).map_err(|_| text.to_string())
+                }
+
+                Ok(Message::Binary(bytes)) => {
+                    rmp_serde::from_slice::<Packet>(&bytes).map_err(|_| format!("{bytes:?}"))
                 }
 
                 Ok(Message::Ping(msg)) => {
-                    let locked_state = state.lock().await;
-                    if let Some(client) = locked_state.clients.get(&id) {
-                        let mut ws = client.ws_session.lock().await;
-                        ws.pong(&msg).await.ok();
-                    }
+                    client.outbox.pong(msg);
+                    continue;
                 }
 
-                Ok(Message::Close(_)) | Err(_) => {
-                    let mut locked_state = state.lock().await;
-                    if let Some(client) = locked_state.clients.remove(&id) {
-                        client.log("Disconnected").await;
+                Ok(Message::Close(_)) | Err(_) => break,
+
+                _ => continue,
+            };
+
+            match packet {
+                Ok(packet) => client.recv(packet, &state).await,
+
+                Err(raw) => {
+                    if !raw.is_empty() {
+                        client.elog(format!("Sent an unparsable packet: {}", raw));
                     }
                 }
-
-                _ => {}
             }
         }
+
+        if outbox.is_evicted() {
+            client.elog("Was dropped for falling behind");
+        }
+
+        // Resuming moves the connection onto the resumed player
+        state
+            .lock()
+            .await
+            .disconnect_client(client.id, &outbox)
+            .await;
     });
 
     Ok(res)
@@ -0,0 +1,96 @@
+use actix_web::rt;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{Mutex, mpsc, oneshot},
+    task::JoinHandle,
+    time::MissedTickBehavior,
+};
+use uuid::Uuid;
+
+use crate::config::GAME_INPUT_CAPACITY;
+use crate::crafting::RecipeRegistry;
+use crate::model::{Game, SharedState};
+use crate::packet::Packet;
+use crate::save;
+
+/// A packet sent by a client playing in the game.
+pub struct GameInput {
+    pub client_id: Uuid,
+    pub packet: Packet,
+}
+
+pub struct GameLoop {
+    shutdown: oneshot::Sender<()>,
+    input: mpsc::Sender<GameInput>,
+    handle: JoinHandle<()>,
+}
+
+impl GameLoop {
+    /// Ticks the game `tick_rate` times per second and handles the packets of its players in
+    /// between, until it is shut down. Only the game is locked for that, the server state is
+    /// only locked to unload the game once nobody played for `idle_timeout`.
+    pub fn spawn(
+        game_arc: Arc<Mutex<Game>>,
+        tick_rate: u32,
+        idle_timeout: Option<Duration>,
+        recipes: Arc<RecipeRegistry>,
+        state: SharedState,
+    ) -> Self {
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+        let (input, mut input_rx) = mpsc::channel::<GameInput>(GAME_INPUT_CAPACITY);
+
+        let handle = rt::spawn(async move {
+            let mut interval = rt::time::interval(Duration::from_secs_f64(1.0 / tick_rate as f64));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+
+                    Some(GameInput { client_id, packet }) = input_rx.recv() => {
+                        game_arc
+                            .lock()
+                            .await
+                            .handle_packet(client_id, packet, &recipes);
+                    }
+
+                    _ = interval.tick() => {
+                        let (game_id, idle_expired) = {
+                            let mut game = game_arc.lock().await;
+                            game.run_tick();
+
+                            let idle_for = game.idle_for();
+                            (game.id, idle_timeout.zip(idle_for).is_some_and(|(timeout, idle)| idle >= timeout))
+                        };
+
+                        if idle_expired
+                            && let Some(game_loop) = save::unload_game(&state, game_id).await
+                        {
+                            // Dropping the loop stops it, as it is the one running this tick
+                            drop(game_loop);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self {
+            shutdown,
+            input,
+            handle,
+        }
+    }
+
+    /// Where the packets of the game's players are sent to, holding up to
+    /// `GAME_INPUT_CAPACITY` of them.
+    pub fn input(&self) -> mpsc::Sender<GameInput> {
+        self.input.clone()
+    }
+
+    /// Stops the loop after the current tick and waits for it to finish.
+    pub async fn shutdown(self) {
+        self.shutdown.send(()).ok();
+        self.handle.await.ok();
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::config::CHUNK_SIZE;
+use crate::model::{Entity, Vec2};
+use crate::settings::GameSettings;
+use crate::spatial::{ChunkCoords, EntityMap, get_chunk_coords_of};
+
+pub fn get_chunk_coords_visible_from(
+    position: Vec2,
+    settings: &GameSettings,
+) -> HashSet<ChunkCoords> {
+    let mut coords = HashSet::new();
+
+    let chunk_size_f: f32 = CHUNK_SIZE as f32;
+    let view_range = settings.view_range;
+    let world_chunks = settings.world_size / CHUNK_SIZE;
+
+    let start_y =
+        (((position.y - view_range - chunk_size_f / 2.0) / chunk_size_f).floor() as i32).max(0);
+    let end_y = (((position.y + view_range - chunk_size_f / 2.0) / chunk_size_f).ceil() as i32)
+        .min(world_chunks);
+    let start_x =
+        (((position.x - view_range - chunk_size_f / 2.0) / chunk_size_f).floor() as i32).max(0);
+    let end_x = (((position.x + view_range - chunk_size_f / 2.0) / chunk_size_f).ceil() as i32)
+        .min(world_chunks);
+
+    for y in start_y..=end_y {
+        for x in start_x..=end_x {
+            coords.insert((x, y));
+        }
+    }
+
+    coords
+}
+
+/// What a client has to load and unload to get in sync with the chunks it can see.
+#[derive(Debug, Default, PartialEq)]
+pub struct ViewChange {
+    pub loaded_chunks: Vec<ChunkCoords>,
+    pub unloaded_chunks: Vec<ChunkCoords>,
+    pub loaded_entities: Vec<Uuid>,
+    pub unloaded_entities: Vec<Uuid>,
+}
+
+/// The clients that have to hear about an entity that moved.
+#[derive(Debug, Default, PartialEq)]
+pub struct MoveInterest {
+    /// Had the entity loaded and still see it
+    pub viewers: Vec<Uuid>,
+    /// Have to load it, it moved into a chunk they see
+    pub loaded: Vec<Uuid>,
+    /// Have to unload it, it moved out of the chunks they see
+    pub unloaded: Vec<Uuid>,
+}
+
+#[derive(Default)]
+struct ClientView {
+    chunks: HashSet<ChunkCoords>,
+    entities: HashSet<Uuid>,
+}
+
+/// Tracks the chunks every client of a game sees and the entities it has loaded. A client has
+/// exactly the entities in its visible chunks loaded, the packets needed to keep it that way
+/// are left to the caller.
+///
+/// Views outlive connections, the player of a dropped connection keeps its view until it
+/// resumes or leaves.
+#[derive(Default)]
+pub struct InterestManager {
+    views: HashMap<Uuid, ClientView>,
+}
+
+impl InterestManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the view of a client to `chunks`, loading the entities in chunks that came into
+    /// view and unloading the ones in chunks that went out of view.
+    pub fn set_view(
+        &mut self,
+        client_id: Uuid,
+        chunks: HashSet<ChunkCoords>,
+        entities: &EntityMap,
+    ) -> ViewChange {
+        let view = self.views.entry(client_id).or_default();
+
+        let loaded_chunks: Vec<_> = chunks.difference(&view.chunks).copied().collect();
+        let unloaded_chunks: Vec<_> = view.chunks.difference(&chunks).copied().collect();
+
+        let unloaded_entities: Vec<_> = entities
+            .in_chunks(&unloaded_chunks)
+            .map(|entity| entity.id)
+            .filter(|id| view.entities.remove(id))
+            .collect();
+        let loaded_entities: Vec<_> = entities
+            .in_chunks(&loaded_chunks)
+            .map(|entity| entity.id)
+            .filter(|id| view.entities.insert(*id))
+            .collect();
+
+        view.chunks = chunks;
+
+        ViewChange {
+            loaded_chunks,
+            unloaded_chunks,
+            loaded_entities,
+            unloaded_entities,
+        }
+    }
+
+    /// Everything a client sees, for a new connection that has nothing loaded yet.
+    pub fn full_view(&self, client_id: Uuid) -> ViewChange {
+        let Some(view) = self.views.get(&client_id) else {
+            return ViewChange::default();
+        };
+
+        ViewChange {
+            loaded_chunks: view.chunks.iter().copied().collect(),
+            loaded_entities: view.entities.iter().copied().collect(),
+            ..ViewChange::default()
+        }
+    }
+
+    pub fn remove_client(&mut self, client_id: Uuid) {
+        self.views.remove(&client_id);
+    }
+
+    /// Loads a new entity for the clients that see its chunk. Returns those clients.
+    pub fn entity_added(&mut self, entity: &Entity) -> Vec<Uuid> {
+        let chunk = get_chunk_coords_of(entity.position);
+
+        self.views
+            .iter_mut()
+            .filter(|(_, view)| view.chunks.contains(&chunk))
+            .filter(|(_, view)| !view.entities.contains(&entity.id))
+            .map(|(client_id, view)| {
+                view.entities.insert(entity.id);
+                *client_id
+            })
+            .collect()
+    }
+
+    /// Loads and unloads an entity that moved to `position` for the clients whose visible
+    /// chunks it entered or left.
+    pub fn entity_moved(&mut self, id: Uuid, position: Vec2) -> MoveInterest {
+        let chunk = get_chunk_coords_of(position);
+        let mut interest = MoveInterest::default();
+
+        for (client_id, view) in &mut self.views {
+            match (view.entities.contains(&id), view.chunks.contains(&chunk)) {
+                (true, true) => interest.viewers.push(*client_id),
+                (true, false) => {
+                    view.entities.remove(&id);
+                    interest.unloaded.push(*client_id);
+                }
+                (false, true) => {
+                    view.entities.insert(id);
+                    interest.loaded.push(*client_id);
+                }
+                (false, false) => {}
+            }
+        }
+
+        interest
+    }
+
+    /// Unloads an entity that left the world. Returns the clients that had it loaded.
+    pub fn entity_removed(&mut self, id: &Uuid) -> Vec<Uuid> {
+        self.views
+            .iter_mut()
+            .filter_map(|(client_id, view)| view.entities.remove(id).then_some(*client_id))
+            .collect()
+    }
+
+    pub fn sees(&self, client_id: Uuid, entity_id: &Uuid) -> bool {
+        self.views
+            .get(&client_id)
+            .is_some_and(|view| view.entities.contains(entity_id))
+    }
+
+    /// The clients that have loaded an entity.
+    pub fn viewers_of(&self, entity_id: &Uuid) -> Vec<Uuid> {
+        self.views
+            .iter()
+            .filter(|(_, view)| view.entities.contains(entity_id))
+            .map(|(client_id, _)| *client_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EntityType;
+    use std::hash::Hash;
+
+    fn set<T: Eq + Hash>(items: Vec<T>) -> HashSet<T> {
+        items.into_iter().collect()
+    }
+
+    fn chunks(coords: &[ChunkCoords]) -> HashSet<ChunkCoords> {
+        coords.iter().copied().collect()
+    }
+
+    fn add_tree(entities: &mut EntityMap, x: f32, y: f32) -> Uuid {
+        let id = Uuid::new_v4();
+        entities.insert(Entity::new(
+            id,
+            Vec2::new(x, y),
+            EntityType::ForestTree,
+            100,
+        ));
+        id
+    }
+
+    /// What a client has loaded according to the changes it was sent.
+    #[derive(Default)]
+    struct ClientState {
+        chunks: HashSet<ChunkCoords>,
+        entities: HashSet<Uuid>,
+    }
+
+    impl ClientState {
+        fn apply(&mut self, change: ViewChange) {
+            for chunk in change.unloaded_chunks {
+                assert!(
+                    self.chunks.remove(&chunk),
+                    "unloaded a chunk it did not have"
+                );
+            }
+            for chunk in change.loaded_chunks {
+                assert!(self.chunks.insert(chunk), "loaded a chunk twice");
+            }
+            for id in change.unloaded_entities {
+                assert!(
+                    self.entities.remove(&id),
+                    "unloaded an entity it did not have"
+                );
+            }
+            for id in change.loaded_entities {
+                assert!(self.entities.insert(id), "loaded an entity twice");
+            }
+        }
+    }
+
+    #[test]
+    fn set_view_loads_and_unloads_chunks_when_the_view_moves() {
+        let mut entities = EntityMap::new();
+        let left = add_tree(&mut entities, 1.0, 1.0);
+        let middle = add_tree(&mut entities, 9.0, 1.0);
+        let right = add_tree(&mut entities, 17.0, 1.0);
+
+        let mut interest = InterestManager::new();
+        let client = Uuid::new_v4();
+
+        let change = interest.set_view(client, chunks(&[(0, 0), (1, 0)]), &entities);
+        assert_eq!(set(change.loaded_chunks), chunks(&[(0, 0), (1, 0)]));
+        assert!(change.unloaded_chunks.is_empty());
+        assert_eq!(set(change.loaded_entities), set(vec![left, middle]));
+        assert!(change.unloaded_entities.is_empty());
+
+        let change = interest.set_view(client, chunks(&[(1, 0), (2, 0)]), &entities);
+        assert_eq!(change.loaded_chunks, vec![(2, 0)]);
+        assert_eq!(change.unloaded_chunks, vec![(0, 0)]);
+        assert_eq!(change.loaded_entities, vec![right]);
+        assert_eq!(change.unloaded_entities, vec![left]);
+
+        assert!(!interest.sees(client, &left));
+        assert!(interest.sees(client, &middle));
+        assert!(interest.sees(client, &right));
+
+        let change = interest.set_view(client, chunks(&[(1, 0), (2, 0)]), &entities);
+        assert_eq!(change, ViewChange::default());
+    }
+
+    #[test]
+    fn entity_moved_across_a_chunk_border_loads_and_unloads_once() {
+        let mut entities = EntityMap::new();
+        let tree = add_tree(&mut entities, 7.0, 1.0);
+
+        let mut interest = InterestManager::new();
+        let (sees_left, sees_right, sees_both) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        interest.set_view(sees_left, chunks(&[(0, 0)]), &entities);
+        interest.set_view(sees_right, chunks(&[(1, 0)]), &entities);
+        interest.set_view(sees_both, chunks(&[(0, 0), (1, 0)]), &entities);
+
+        let moved = interest.entity_moved(tree, Vec2::new(9.0, 1.0));
+        assert_eq!(
+            moved,
+            MoveInterest {
+                viewers: vec![sees_both],
+                loaded: vec![sees_right],
+                unloaded: vec![sees_left],
+            }
+        );
+
+        // Moving on within the same chunk neither loads nor unloads it again
+        let moved = interest.entity_moved(tree, Vec2::new(10.0, 1.0));
+        assert!(moved.loaded.is_empty());
+        assert!(moved.unloaded.is_empty());
+        assert_eq!(set(moved.viewers), set(vec![sees_right, sees_both]));
+
+        assert!(!interest.sees(sees_left, &tree));
+        assert_eq!(
+            set(interest.viewers_of(&tree)),
+            set(vec![sees_right, sees_both])
+        );
+    }
+
+    #[test]
+    fn entity_removed_clears_every_viewer() {
+        let mut entities = EntityMap::new();
+        let tree = add_tree(&mut entities, 1.0, 1.0);
+
+        let mut interest = InterestManager::new();
+        let (near, far) = (Uuid::new_v4(), Uuid::new_v4());
+        let also_near = Uuid::new_v4();
+
+        interest.set_view(near, chunks(&[(0, 0)]), &entities);
+        interest.set_view(also_near, chunks(&[(0, 0), (1, 0)]), &entities);
+        interest.set_view(far, chunks(&[(5, 5)]), &entities);
+
+        assert_eq!(
+            set(interest.entity_removed(&tree)),
+            set(vec![near, also_near])
+        );
+        assert!(interest.viewers_of(&tree).is_empty());
+        assert!(interest.entity_removed(&tree).is_empty());
+    }
+
+    #[test]
+    fn full_view_matches_what_the_client_was_sent() {
+        let mut entities = EntityMap::new();
+        add_tree(&mut entities, 1.0, 1.0);
+        add_tree(&mut entities, 9.0, 9.0);
+        let wandering = add_tree(&mut entities, 12.0, 1.0);
+
+        let mut interest = InterestManager::new();
+        let client = Uuid::new_v4();
+        let mut sent = ClientState::default();
+
+        sent.apply(interest.set_view(client, chunks(&[(0, 0), (1, 0)]), &entities));
+
+        let dropped = Entity::new(
+            Uuid::new_v4(),
+            Vec2::new(2.0, 2.0),
+            EntityType::ForestTree,
+            100,
+        );
+        entities.insert(dropped.clone());
+        if interest.entity_added(&dropped).contains(&client) {
+            sent.entities.insert(dropped.id);
+        }
+
+        entities.set_position(&wandering, Vec2::new(20.0, 1.0));
+        let moved = interest.entity_moved(wandering, Vec2::new(20.0, 1.0));
+        if moved.unloaded.contains(&client) {
+            sent.entities.remove(&wandering);
+        }
+
+        sent.apply(interest.set_view(client, chunks(&[(1, 0), (1, 1), (2, 0)]), &entities));
+
+        // A resumed connection starts from nothing and is sent the full view
+        let mut resumed = ClientState::default();
+        resumed.apply(interest.full_view(client));
+
+        assert_eq!(resumed.chunks, sent.chunks);
+        assert_eq!(resumed.entities, sent.entities);
+        assert!(resumed.entities.contains(&wandering));
+
+        interest.remove_client(client);
+        assert_eq!(interest.full_view(client), ViewChange::default());
+    }
+}
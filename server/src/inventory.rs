@@ -0,0 +1,661 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq)]
+pub enum ToolType {
+    Sword,
+    Pickaxe,
+    Axe,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum ToolMaterial {
+    Wood,
+    Stone,
+    Iron,
+    Gold,
+    Diamond,
+}
+
+impl ToolMaterial {
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            ToolMaterial::Wood => 1.0,
+            ToolMaterial::Stone => 1.5,
+            ToolMaterial::Iron => 2.0,
+            ToolMaterial::Gold => 1.5,
+            ToolMaterial::Diamond => 3.0,
+        }
+    }
+
+    pub fn gathering_speed(&self) -> f32 {
+        match self {
+            ToolMaterial::Wood => 1.0,
+            ToolMaterial::Stone => 1.5,
+            ToolMaterial::Iron => 2.0,
+            ToolMaterial::Gold => 3.0,
+            ToolMaterial::Diamond => 2.5,
+        }
+    }
+
+    pub fn max_durability(&self) -> u32 {
+        match self {
+            ToolMaterial::Wood => 60,
+            ToolMaterial::Stone => 130,
+            ToolMaterial::Iron => 250,
+            ToolMaterial::Gold => 40,
+            ToolMaterial::Diamond => 1500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub enum Item {
+    #[serde(rename = "wooden_sword")]
+    WoodenSword,
+
+    #[serde(rename = "wooden_pickaxe")]
+    WoodenPickaxe,
+
+    #[serde(rename = "wooden_axe")]
+    WoodenAxe,
+
+    #[serde(rename = "stone_sword")]
+    StoneSword,
+
+    #[serde(rename = "stone_pickaxe")]
+    StonePickaxe,
+
+    #[serde(rename = "stone_axe")]
+    StoneAxe,
+
+    #[serde(rename = "iron_sword")]
+    IronSword,
+
+    #[serde(rename = "iron_pickaxe")]
+    IronPickaxe,
+
+    #[serde(rename = "iron_axe")]
+    IronAxe,
+
+    #[serde(rename = "gold_sword")]
+    GoldSword,
+
+    #[serde(rename = "gold_pickaxe")]
+    GoldPickaxe,
+
+    #[serde(rename = "gold_axe")]
+    GoldAxe,
+
+    #[serde(rename = "diamond_sword")]
+    DiamondSword,
+
+    #[serde(rename = "diamond_pickaxe")]
+    DiamondPickaxe,
+
+    #[serde(rename = "diamond_axe")]
+    DiamondAxe,
+
+    #[serde(rename = "wood")]
+    Wood,
+
+    #[serde(rename = "stone")]
+    Stone,
+
+    #[serde(rename = "fiber")]
+    Fiber,
+
+    #[serde(rename = "cactus")]
+    Cactus,
+
+    #[serde(rename = "ice")]
+    Ice,
+
+    #[serde(rename = "shell")]
+    Shell,
+
+    #[serde(rename = "iron_ore")]
+    IronOre,
+
+    #[serde(rename = "gold_ore")]
+    GoldOre,
+
+    #[serde(rename = "diamond")]
+    Diamond,
+
+    #[serde(rename = "berries")]
+    Berries,
+
+    #[serde(rename = "clam")]
+    Clam,
+
+    #[serde(rename = "meat")]
+    Meat,
+}
+
+impl Item {
+    pub fn tool_type(&self) -> Option<ToolType> {
+        match self {
+            Item::WoodenSword
+            | Item::StoneSword
+            | Item::IronSword
+            | Item::GoldSword
+            | Item::DiamondSword => Some(ToolType::Sword),
+            Item::WoodenPickaxe
+            | Item::StonePickaxe
+            | Item::IronPickaxe
+            | Item::GoldPickaxe
+            | Item::DiamondPickaxe => Some(ToolType::Pickaxe),
+            Item::WoodenAxe | Item::StoneAxe | Item::IronAxe | Item::GoldAxe | Item::DiamondAxe => {
+                Some(ToolType::Axe)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn tool_material(&self) -> Option<ToolMaterial> {
+        match self {
+            Item::WoodenSword | Item::WoodenPickaxe | Item::WoodenAxe => Some(ToolMaterial::Wood),
+            Item::StoneSword | Item::StonePickaxe | Item::StoneAxe => Some(ToolMaterial::Stone),
+            Item::IronSword | Item::IronPickaxe | Item::IronAxe => Some(ToolMaterial::Iron),
+            Item::GoldSword | Item::GoldPickaxe | Item::GoldAxe => Some(ToolMaterial::Gold),
+            Item::DiamondSword | Item::DiamondPickaxe | Item::DiamondAxe => {
+                Some(ToolMaterial::Diamond)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn max_durability(&self) -> Option<u32> {
+        self.tool_material()
+            .map(|material| material.max_durability())
+    }
+
+    pub fn food_value(&self) -> Option<i32> {
+        match self {
+            Item::Berries => Some(10),
+            Item::Clam => Some(25),
+            Item::Meat => Some(30),
+            _ => None,
+        }
+    }
+
+    pub fn max_stack_size(&self) -> u32 {
+        if self.tool_type().is_some() {
+            return 1;
+        }
+
+        match self {
+            Item::Wood | Item::Stone | Item::Fiber => 64,
+            Item::Cactus | Item::Ice | Item::IronOre | Item::GoldOre => 32,
+            Item::Berries | Item::Clam | Item::Meat => 32,
+            Item::Shell | Item::Diamond => 16,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub durability: Option<u32>,
+}
+
+impl ItemStack {
+    pub fn new(item: Item, count: u32) -> Self {
+        Self {
+            item,
+            count,
+            durability: item.max_durability(),
+        }
+    }
+
+    fn with_count(self, count: u32) -> Self {
+        Self { count, ..self }
+    }
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    InvalidSlot,
+    EmptySlot,
+    SlotOccupied,
+    InvalidCount,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    pub selected: i32,
+}
+
+impl Inventory {
+    pub fn hand_item(&self) -> Option<Item> {
+        self.slots
+            .get(self.selected as usize)
+            .copied()
+            .flatten()
+            .map(|stack| stack.item)
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Takes `count` of `item` out of the inventory, emptying the last stacks first.
+    /// Does nothing and returns `false` if there are not enough of them.
+    pub fn remove(&mut self, item: Item, mut count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+
+        for slot in self.slots.iter_mut().rev() {
+            if count == 0 {
+                break;
+            }
+
+            if let Some(stack) = slot
+                && stack.item == item
+            {
+                let taken = stack.count.min(count);
+                stack.count -= taken;
+                count -= taken;
+
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn slot_index(&self, slot: i32) -> Result<usize, InventoryError> {
+        usize::try_from(slot)
+            .ok()
+            .filter(|index| *index < self.slots.len())
+            .ok_or(InventoryError::InvalidSlot)
+    }
+
+    /// Puts `stack` into the inventory, topping up existing stacks of the same item before
+    /// using empty slots. Returns how many items did not fit.
+    pub fn add(&mut self, mut stack: ItemStack) -> u32 {
+        let max_stack_size = stack.item.max_stack_size();
+
+        for slot in self.slots.iter_mut().flatten() {
+            if stack.count == 0 {
+                break;
+            }
+
+            if slot.item == stack.item && slot.count < max_stack_size {
+                let moved = (max_stack_size - slot.count).min(stack.count);
+                slot.count += moved;
+                stack.count -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if stack.count == 0 {
+                break;
+            }
+
+            if slot.is_none() {
+                let moved = stack.count.min(max_stack_size);
+                *slot = Some(ItemStack {
+                    count: moved,
+                    durability: stack.durability.or(stack.item.max_durability()),
+                    ..stack
+                });
+                stack.count -= moved;
+            }
+        }
+
+        stack.count
+    }
+
+    /// Takes a single item out of `slot`.
+    pub fn take_one(&mut self, slot: i32) -> Result<Item, InventoryError> {
+        let index = self.slot_index(slot)?;
+        let stack = self.slots[index].ok_or(InventoryError::EmptySlot)?;
+
+        self.slots[index] = (stack.count > 1).then(|| stack.with_count(stack.count - 1));
+
+        Ok(stack.item)
+    }
+
+    /// Moves the whole stack from `from` onto `to`, merging it into a stack of the same item
+    /// (as far as it fits) or swapping the two slots otherwise.
+    pub fn move_stack(&mut self, from: i32, to: i32) -> Result<(), InventoryError> {
+        let from = self.slot_index(from)?;
+        let to = self.slot_index(to)?;
+
+        if from == to {
+            return Ok(());
+        }
+
+        let source = self.slots[from].ok_or(InventoryError::EmptySlot)?;
+
+        match self.slots[to] {
+            Some(target)
+                if target.item == source.item && target.count < source.item.max_stack_size() =>
+            {
+                let moved = (source.item.max_stack_size() - target.count).min(source.count);
+
+                self.slots[to] = Some(target.with_count(target.count + moved));
+                self.slots[from] =
+                    (source.count > moved).then(|| source.with_count(source.count - moved));
+            }
+            _ => self.slots.swap(from, to),
+        }
+
+        Ok(())
+    }
+
+    /// Moves `count` items from `from` into `to`, which has to be empty or hold the same item.
+    pub fn split_stack(&mut self, from: i32, to: i32, count: u32) -> Result<(), InventoryError> {
+        let from = self.slot_index(from)?;
+        let to = self.slot_index(to)?;
+
+        let source = self.slots[from].ok_or(InventoryError::EmptySlot)?;
+
+        if from == to || count == 0 || count > source.count {
+            return Err(InventoryError::InvalidCount);
+        }
+
+        let target_count = match self.slots[to] {
+            None => 0,
+            Some(target) if target.item == source.item => target.count,
+            Some(_) => return Err(InventoryError::SlotOccupied),
+        };
+
+        if target_count + count > source.item.max_stack_size() {
+            return Err(InventoryError::InvalidCount);
+        }
+
+        self.slots[to] = Some(source.with_count(target_count + count));
+        self.slots[from] = (source.count > count).then(|| source.with_count(source.count - count));
+
+        Ok(())
+    }
+
+    /// Wears down the tool in the selected slot by one use, removing it once its durability
+    /// runs out. Returns the tool if it broke.
+    pub fn wear_hand_item(&mut self) -> Option<Item> {
+        let slot = self.slots.get_mut(self.selected as usize)?;
+        let stack = slot.as_mut()?;
+        let durability = stack.durability.as_mut()?;
+
+        *durability = durability.saturating_sub(1);
+
+        if *durability == 0 {
+            let item = stack.item;
+            *slot = None;
+            return Some(item);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory(slots: Vec<Option<(Item, u32)>>) -> Inventory {
+        Inventory {
+            slots: slots
+                .into_iter()
+                .map(|slot| slot.map(|(item, count)| ItemStack::new(item, count)))
+                .collect(),
+            selected: 0,
+        }
+    }
+
+    fn contents(inventory: &Inventory) -> Vec<Option<(Item, u32)>> {
+        inventory
+            .slots
+            .iter()
+            .map(|slot| slot.map(|stack| (stack.item, stack.count)))
+            .collect()
+    }
+
+    #[test]
+    fn add_tops_up_partial_stacks_before_using_empty_slots() {
+        let mut inventory = inventory(vec![Some((Item::Wood, 60)), None, Some((Item::Wood, 10))]);
+
+        assert_eq!(inventory.add(ItemStack::new(Item::Wood, 20)), 0);
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Wood, 64)), None, Some((Item::Wood, 26))]
+        );
+    }
+
+    #[test]
+    fn add_splits_more_than_a_stack_across_empty_slots() {
+        let mut inventory = inventory(vec![None, Some((Item::Wood, 1)), None, None]);
+
+        assert_eq!(inventory.add(ItemStack::new(Item::Stone, 150)), 0);
+        assert_eq!(
+            contents(&inventory),
+            vec![
+                Some((Item::Stone, 64)),
+                Some((Item::Wood, 1)),
+                Some((Item::Stone, 64)),
+                Some((Item::Stone, 22)),
+            ]
+        );
+    }
+
+    #[test]
+    fn add_returns_what_does_not_fit_into_a_full_inventory() {
+        let mut inventory = inventory(vec![Some((Item::Berries, 30)), Some((Item::Wood, 1))]);
+
+        assert_eq!(inventory.add(ItemStack::new(Item::Berries, 10)), 8);
+        assert_eq!(inventory.add(ItemStack::new(Item::Stone, 5)), 5);
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Berries, 32)), Some((Item::Wood, 1))]
+        );
+    }
+
+    #[test]
+    fn tools_do_not_stack_and_keep_their_durability() {
+        let mut inventory = inventory(vec![Some((Item::StoneAxe, 1)), None, None]);
+
+        let worn = ItemStack {
+            durability: Some(5),
+            ..ItemStack::new(Item::StoneAxe, 1)
+        };
+
+        assert_eq!(inventory.add(worn), 0);
+        assert_eq!(inventory.add(ItemStack::new(Item::StoneAxe, 1)), 0);
+        assert_eq!(inventory.add(ItemStack::new(Item::StoneAxe, 1)), 1);
+
+        assert_eq!(inventory.count(Item::StoneAxe), 3);
+        assert_eq!(inventory.slots[0].unwrap().durability, Some(130));
+        assert_eq!(inventory.slots[1].unwrap().durability, Some(5));
+        assert_eq!(inventory.slots[2].unwrap().durability, Some(130));
+    }
+
+    #[test]
+    fn remove_empties_the_last_stacks_first() {
+        let mut inventory = inventory(vec![
+            Some((Item::Wood, 64)),
+            Some((Item::Stone, 3)),
+            Some((Item::Wood, 10)),
+        ]);
+
+        assert!(inventory.remove(Item::Wood, 20));
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Wood, 54)), Some((Item::Stone, 3)), None]
+        );
+    }
+
+    #[test]
+    fn remove_does_nothing_without_enough_items() {
+        let mut inventory = inventory(vec![Some((Item::Wood, 5)), Some((Item::Wood, 5))]);
+
+        assert!(!inventory.remove(Item::Wood, 11));
+        assert!(!inventory.remove(Item::Stone, 1));
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Wood, 5)), Some((Item::Wood, 5))]
+        );
+    }
+
+    #[test]
+    fn take_one_empties_the_slot_with_the_last_item() {
+        let mut inventory = inventory(vec![Some((Item::Meat, 2)), None]);
+
+        assert!(matches!(inventory.take_one(0), Ok(Item::Meat)));
+        assert_eq!(contents(&inventory)[0], Some((Item::Meat, 1)));
+        assert!(matches!(inventory.take_one(0), Ok(Item::Meat)));
+        assert_eq!(contents(&inventory)[0], None);
+
+        assert!(matches!(
+            inventory.take_one(0),
+            Err(InventoryError::EmptySlot)
+        ));
+        assert!(matches!(
+            inventory.take_one(-1),
+            Err(InventoryError::InvalidSlot)
+        ));
+        assert!(matches!(
+            inventory.take_one(2),
+            Err(InventoryError::InvalidSlot)
+        ));
+    }
+
+    #[test]
+    fn move_stack_merges_as_much_as_fits() {
+        let mut inventory = inventory(vec![Some((Item::Wood, 50)), Some((Item::Wood, 30))]);
+
+        inventory.move_stack(0, 1).unwrap();
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Wood, 16)), Some((Item::Wood, 64))]
+        );
+
+        inventory.move_stack(0, 1).unwrap();
+        assert_eq!(
+            contents(&inventory),
+            vec![Some((Item::Wood, 64)), Some((Item::Wood, 16))],
+            "a full target is swapped"
+        );
+    }
+
+    #[test]
+    fn move_stack_swaps_different_items_and_tools() {
+        let mut inventory = inventory(vec![
+            Some((Item::Wood, 5)),
+            Some((Item::Stone, 7)),
+            Some((Item::WoodenSword, 1)),
+            Some((Item::WoodenSword, 1)),
+            None,
+        ]);
+        inventory.slots[3].as_mut().unwrap().durability = Some(1);
+
+        inventory.move_stack(0, 1).unwrap();
+        inventory.move_stack(2, 3).unwrap();
+        inventory.move_stack(1, 4).unwrap();
+
+        assert_eq!(
+            contents(&inventory),
+            vec![
+                Some((Item::Stone, 7)),
+                None,
+                Some((Item::WoodenSword, 1)),
+                Some((Item::WoodenSword, 1)),
+                Some((Item::Wood, 5)),
+            ]
+        );
+        assert_eq!(inventory.slots[2].unwrap().durability, Some(1));
+
+        assert!(inventory.move_stack(0, 0).is_ok());
+        assert!(matches!(
+            inventory.move_stack(1, 0),
+            Err(InventoryError::EmptySlot)
+        ));
+        assert!(matches!(
+            inventory.move_stack(0, 5),
+            Err(InventoryError::InvalidSlot)
+        ));
+    }
+
+    #[test]
+    fn split_stack_moves_part_of_a_stack() {
+        let mut inventory = inventory(vec![Some((Item::Wood, 10)), None, Some((Item::Wood, 60))]);
+
+        inventory.split_stack(0, 1, 4).unwrap();
+        inventory.split_stack(0, 2, 4).unwrap();
+        assert_eq!(
+            contents(&inventory),
+            vec![
+                Some((Item::Wood, 2)),
+                Some((Item::Wood, 4)),
+                Some((Item::Wood, 64)),
+            ]
+        );
+
+        inventory.split_stack(0, 1, 2).unwrap();
+        assert_eq!(
+            contents(&inventory)[..2],
+            [None, Some((Item::Wood, 6))],
+            "splitting off everything empties the slot"
+        );
+    }
+
+    #[test]
+    fn split_stack_rejects_what_does_not_fit() {
+        let mut inventory = inventory(vec![
+            Some((Item::Wood, 10)),
+            Some((Item::Stone, 1)),
+            Some((Item::Wood, 60)),
+            None,
+        ]);
+
+        let invalid_count = |result| matches!(result, Err(InventoryError::InvalidCount));
+
+        assert!(invalid_count(inventory.split_stack(0, 2, 5)));
+        assert!(invalid_count(inventory.split_stack(0, 3, 0)));
+        assert!(invalid_count(inventory.split_stack(0, 3, 11)));
+        assert!(invalid_count(inventory.split_stack(0, 0, 1)));
+        assert!(matches!(
+            inventory.split_stack(0, 1, 1),
+            Err(InventoryError::SlotOccupied)
+        ));
+        assert!(matches!(
+            inventory.split_stack(3, 0, 1),
+            Err(InventoryError::EmptySlot)
+        ));
+
+        assert_eq!(inventory.count(Item::Wood), 70);
+        assert_eq!(contents(&inventory)[3], None);
+    }
+
+    #[test]
+    fn wear_hand_item_breaks_the_tool_once_it_is_worn_out() {
+        let mut inventory = inventory(vec![Some((Item::GoldAxe, 1)), Some((Item::Wood, 3)), None]);
+        inventory.slots[0].as_mut().unwrap().durability = Some(2);
+
+        assert_eq!(inventory.wear_hand_item(), None);
+        assert_eq!(inventory.slots[0].unwrap().durability, Some(1));
+        assert_eq!(inventory.wear_hand_item(), Some(Item::GoldAxe));
+        assert_eq!(contents(&inventory)[0], None);
+
+        // Only tools wear down
+        inventory.selected = 1;
+        assert_eq!(inventory.wear_hand_item(), None);
+        assert_eq!(contents(&inventory)[1], Some((Item::Wood, 3)));
+
+        inventory.selected = 2;
+        assert_eq!(inventory.wear_hand_item(), None);
+    }
+}
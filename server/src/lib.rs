@@ -0,0 +1,23 @@
+pub mod accounts;
+pub mod config;
+pub mod crafting;
+pub mod game_loop;
+pub mod interest;
+pub mod inventory;
+pub mod mob;
+pub mod model;
+pub mod outbox;
+pub mod packet;
+pub mod save;
+pub mod settings;
+pub mod spatial;
+pub mod terrain;
+
+pub mod endpoints {
+    pub mod account;
+    pub mod game;
+    pub mod games;
+    pub mod metrics;
+    pub mod new_game;
+    pub mod ws;
+}
@@ -1,43 +1,100 @@
 use actix_files::Files;
 use actix_web::{App, HttpServer, web};
-use std::sync::Arc;
+use clap::Parser;
+use log::info;
+use std::{process, sync::Arc};
 use tokio::sync::Mutex;
 
-pub mod config;
-pub mod model;
-pub mod packet;
-pub mod terrain;
-
-pub mod endpoints {
-    pub mod game;
-    pub mod new_game;
-    pub mod ws;
-}
-
-use model::{ServerState, SharedState};
+use hexhavoc_server::accounts::AccountStore;
+use hexhavoc_server::config::{Args, ServerConfig};
+use hexhavoc_server::crafting::RecipeRegistry;
+use hexhavoc_server::model::{ServerState, SharedState};
+use hexhavoc_server::{endpoints, save};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let address = "0.0.0.0";
-    let port = 9872;
+    let config = match ServerConfig::load(Args::parse()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(2);
+        }
+    };
+
+    let recipes = match &config.recipes_file {
+        Some(path) => match RecipeRegistry::load(path) {
+            Ok(recipes) => recipes,
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(2);
+            }
+        },
+        None => RecipeRegistry::default(),
+    };
+
+    env_logger::Builder::new()
+        .filter_level(config.log_level)
+        .init();
+
+    let address = config.address.clone();
+    let port = config.port;
+    let static_dir = config.static_dir.clone();
+
+    info!("Starting HexHavoc on http://{address}:{port}");
+
+    let mut initial_state = ServerState::default();
+    initial_state.recipes = Arc::new(recipes);
+    initial_state.accounts = AccountStore::load(&config.accounts_file)?;
 
-    println!("Starting HexHavoc on http://{address}:{port}");
+    for game in save::load_unloaded_games(&config.save_dir) {
+        initial_state.unloaded_games.insert(game.name.clone(), game);
+    }
 
-    let state: SharedState = Arc::new(Mutex::new(ServerState::default()));
+    initial_state.config = config;
+
+    let state: SharedState = Arc::new(Mutex::new(initial_state));
+
+    let autosave = save::spawn_autosave(state.clone());
+
+    let server_state = state.clone();
 
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(state.clone()))
             .service(endpoints::new_game::create_new_game)
+            .service(endpoints::account::register)
+            .service(endpoints::account::login)
             .service(endpoints::game::game)
+            .service(endpoints::games::list_games)
+            .service(endpoints::games::delete_game)
+            .service(endpoints::metrics::metrics)
             .service(endpoints::ws::ws)
             .service(
-                Files::new("/", "client/dist")
+                Files::new("/", &static_dir)
                     .prefer_utf8(true)
                     .index_file("index.html"),
             )
     })
-    .bind((address, port))?
+    .bind((address.as_str(), port))?
     .run()
-    .await
+    .await?;
+
+    autosave.abort();
+    save::save_games(&server_state).await;
+
+    let game_loops: Vec<_> = {
+        let mut state = server_state.lock().await;
+        let game_ids: Vec<_> = state.games.keys().copied().collect();
+
+        game_ids
+            .into_iter()
+            .filter_map(|id| state.remove_game(id))
+            .collect()
+    };
+
+    for game_loop in game_loops {
+        game_loop.shutdown().await;
+    }
+
+    Ok(())
 }
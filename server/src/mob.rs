@@ -0,0 +1,55 @@
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::model::{EntityType, Vec2};
+use crate::terrain::TileType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Temperament {
+    Hostile,
+    Neutral,
+    Passive,
+}
+
+pub struct MobStats {
+    pub name: &'static str,
+    pub max_health: i32,
+    pub speed: f32,
+    pub damage: i32,
+    pub aggro_range: f32,
+    pub temperament: Temperament,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Behaviour {
+    Idle { until: Instant },
+    Wander { target: Vec2 },
+    Chase { target: Uuid },
+    Flee { from: Uuid, until: Instant },
+}
+
+pub struct Mob {
+    pub behaviour: Behaviour,
+    pub last_attack: Option<Instant>,
+}
+
+impl Default for Mob {
+    fn default() -> Self {
+        Self {
+            behaviour: Behaviour::Idle {
+                until: Instant::now(),
+            },
+            last_attack: None,
+        }
+    }
+}
+
+pub fn mob_for_tile(tile: TileType) -> Option<EntityType> {
+    match tile {
+        TileType::Forest => Some(EntityType::Wolf),
+        TileType::Desert => Some(EntityType::Scorpion),
+        TileType::Snow | TileType::Ice => Some(EntityType::PolarBear),
+        TileType::Beach => Some(EntityType::Crab),
+        _ => None,
+    }
+}
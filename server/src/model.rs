@@ -1,83 +1,43 @@
+use log::{debug, error, info, warn};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, mpsc};
 use uuid::Uuid;
 
+use crate::accounts::AccountStore;
 use crate::config::*;
-use crate::packet::{ErrorPacket, Packet};
+use crate::crafting::{AvailableRecipe, CraftError, RecipeRegistry};
+use crate::game_loop::{GameInput, GameLoop};
+use crate::interest::{InterestManager, ViewChange, get_chunk_coords_visible_from};
+use crate::inventory::{Inventory, InventoryError, Item, ItemStack, ToolType};
+use crate::mob::{Behaviour, Mob, MobStats, Temperament, mob_for_tile};
+use crate::outbox::{Outbox, OutboxMetrics};
+use crate::packet::{EntityMovement, Packet};
+use crate::save::{DamagedEntity, GameSave, HarvestedEntity, SavedPlayer};
+use crate::settings::GameSettings;
+use crate::spatial::EntityMap;
 use crate::terrain::{TerrainChunk, TerrainGenerator, TileType};
 
 /// STRUCT
 
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub struct Vec2 {
-    x: f32,
-    y: f32,
+    pub x: f32,
+    pub y: f32,
 }
 
 impl Vec2 {
     pub fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
-}
-
-#[derive(PartialEq, Eq)]
-enum ToolType {
-    Sword,
-    Pickaxe,
-    Axe,
-}
-
-#[derive(PartialEq, Eq)]
-enum ToolMaterial {
-    Iron,
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
-pub enum Item {
-    #[serde(rename = "iron_sword")]
-    IronSword,
-
-    #[serde(rename = "iron_pickaxe")]
-    IronPickaxe,
-
-    #[serde(rename = "iron_axe")]
-    IronAxe,
-}
-
-impl Item {
-    fn tool_type(&self) -> Option<ToolType> {
-        Some(match self {
-            Item::IronSword => ToolType::Sword,
-            Item::IronPickaxe => ToolType::Pickaxe,
-            Item::IronAxe => ToolType::Axe,
-        })
-    }
-
-    fn tool_material(&self) -> Option<ToolMaterial> {
-        Some(match self {
-            Item::IronSword | Item::IronPickaxe | Item::IronAxe => ToolMaterial::Iron,
-        })
-    }
-}
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Inventory {
-    pub slots: Vec<Option<Item>>,
-    pub selected: i32,
-}
-
-impl Inventory {
-    fn hand_item(&self) -> Option<Item> {
-        if let Some(item) = self.slots.get(self.selected as usize) {
-            item.clone()
-        } else {
-            None
-        }
+    pub fn distance(&self, other: Vec2) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
     }
 }
 
@@ -86,6 +46,13 @@ pub struct EntityPlayer {
     pub username: String,
     pub skin: i32,
     pub inventory: Inventory,
+    pub hunger: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EntityDroppedItem {
+    #[serde(flatten)]
+    pub stack: ItemStack,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -93,6 +60,9 @@ pub enum EntityType {
     #[serde(rename = "player")]
     Player(EntityPlayer),
 
+    #[serde(rename = "dropped_item")]
+    DroppedItem(EntityDroppedItem),
+
     #[serde(rename = "forest_tree")]
     ForestTree,
 
@@ -128,12 +98,25 @@ pub enum EntityType {
 
     #[serde(rename = "sea_shell")]
     SeaShell,
+
+    #[serde(rename = "wolf")]
+    Wolf,
+
+    #[serde(rename = "scorpion")]
+    Scorpion,
+
+    #[serde(rename = "polar_bear")]
+    PolarBear,
+
+    #[serde(rename = "crab")]
+    Crab,
 }
 
 impl EntityType {
     fn best_damaging_tool(&self) -> ToolType {
         match self {
             EntityType::Player(_) => ToolType::Sword,
+            EntityType::DroppedItem(_) => ToolType::Sword,
             EntityType::ForestTree => ToolType::Axe,
             EntityType::SpruceTree => ToolType::Axe,
             EntityType::JungleTree => ToolType::Axe,
@@ -146,8 +129,134 @@ impl EntityType {
             EntityType::TreeLog => ToolType::Axe,
             EntityType::TallGrass => ToolType::Axe,
             EntityType::SeaShell => ToolType::Pickaxe,
+            EntityType::Wolf => ToolType::Sword,
+            EntityType::Scorpion => ToolType::Sword,
+            EntityType::PolarBear => ToolType::Sword,
+            EntityType::Crab => ToolType::Sword,
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            self,
+            EntityType::ForestTree
+                | EntityType::SpruceTree
+                | EntityType::JungleTree
+                | EntityType::Cactus
+                | EntityType::IceSpike
+                | EntityType::Stone
+                | EntityType::BigStone
+        )
+    }
+
+    pub fn is_attackable(&self) -> bool {
+        !matches!(self, EntityType::DroppedItem(_))
+    }
+
+    fn regrowth_delay(&self) -> Option<Duration> {
+        let seconds = match self {
+            EntityType::Player(_) | EntityType::DroppedItem(_) => return None,
+            EntityType::TallGrass | EntityType::Bush => 30,
+            EntityType::SeaShell => 60,
+            EntityType::Cactus | EntityType::TreeStump | EntityType::TreeLog => 90,
+            EntityType::ForestTree | EntityType::SpruceTree | EntityType::JungleTree => 120,
+            EntityType::IceSpike | EntityType::Stone => 180,
+            EntityType::BigStone => 300,
+            EntityType::Wolf | EntityType::Scorpion | EntityType::PolarBear | EntityType::Crab => {
+                return None;
+            }
+        };
+
+        Some(Duration::from_secs(seconds))
+    }
+
+    fn loot(&self) -> Vec<ItemStack> {
+        match self {
+            EntityType::Player(player) => {
+                player.inventory.slots.iter().flatten().copied().collect()
+            }
+            EntityType::DroppedItem(_) => vec![],
+            EntityType::ForestTree => vec![ItemStack::new(Item::Wood, 3)],
+            EntityType::SpruceTree => vec![ItemStack::new(Item::Wood, 3)],
+            EntityType::JungleTree => vec![ItemStack::new(Item::Wood, 3)],
+            EntityType::Cactus => vec![ItemStack::new(Item::Cactus, 2)],
+            EntityType::TreeStump => vec![ItemStack::new(Item::Wood, 2)],
+            EntityType::IceSpike => vec![ItemStack::new(Item::Ice, 2)],
+            EntityType::Bush => vec![
+                ItemStack::new(Item::Fiber, 1),
+                ItemStack::new(Item::Berries, 2),
+            ],
+            EntityType::Stone => vec![ItemStack::new(Item::Stone, 2)],
+            EntityType::BigStone => {
+                let mut rng = rand::rng();
+                let mut loot = vec![ItemStack::new(Item::Stone, 4)];
+
+                for (ore, chance) in [
+                    (Item::IronOre, 0.3),
+                    (Item::GoldOre, 0.1),
+                    (Item::Diamond, 0.03),
+                ] {
+                    if rng.random_bool(chance) {
+                        loot.push(ItemStack::new(ore, 1));
+                    }
+                }
+
+                loot
+            }
+            EntityType::TreeLog => vec![ItemStack::new(Item::Wood, 2)],
+            EntityType::TallGrass => vec![ItemStack::new(Item::Fiber, 2)],
+            EntityType::SeaShell => vec![
+                ItemStack::new(Item::Shell, 1),
+                ItemStack::new(Item::Clam, 1),
+            ],
+            EntityType::Wolf => vec![ItemStack::new(Item::Meat, 1)],
+            EntityType::Scorpion => vec![],
+            EntityType::PolarBear => vec![ItemStack::new(Item::Meat, 3)],
+            EntityType::Crab => vec![ItemStack::new(Item::Clam, 1)],
         }
     }
+
+    fn mob_stats(&self) -> Option<MobStats> {
+        match self {
+            EntityType::Wolf => Some(MobStats {
+                name: "wolf",
+                max_health: 80,
+                speed: 5.0,
+                damage: 15,
+                aggro_range: 8.0,
+                temperament: Temperament::Hostile,
+            }),
+            EntityType::Scorpion => Some(MobStats {
+                name: "scorpion",
+                max_health: 60,
+                speed: 3.0,
+                damage: 20,
+                aggro_range: 5.0,
+                temperament: Temperament::Hostile,
+            }),
+            EntityType::PolarBear => Some(MobStats {
+                name: "polar bear",
+                max_health: 200,
+                speed: 4.0,
+                damage: 30,
+                aggro_range: 10.0,
+                temperament: Temperament::Neutral,
+            }),
+            EntityType::Crab => Some(MobStats {
+                name: "crab",
+                max_health: 40,
+                speed: 3.0,
+                damage: 0,
+                aggro_range: 3.0,
+                temperament: Temperament::Passive,
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_mob(&self) -> bool {
+        self.mob_stats().is_some()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -158,13 +267,143 @@ pub struct Entity {
     pub health: i32,
 }
 
-pub struct Game {
+#[derive(Debug)]
+pub enum MoveError {
+    OutOfBounds,
+    TooFast,
+    Impassable,
+    Collision,
+}
+
+#[derive(Debug)]
+pub enum PickupError {
+    NotFound,
+    NotAnItem,
+    OutOfRange,
+    InventoryFull,
+}
+
+struct MoveBudget {
+    allowance: f32,
+    last_update: Instant,
+}
+
+pub enum DeathCause {
+    Player(String),
+    Mob(&'static str),
+    Starvation,
+}
+
+impl DeathCause {
+    fn killer(&self) -> Option<String> {
+        match self {
+            DeathCause::Player(username) => Some(username.clone()),
+            DeathCause::Mob(name) => Some(name.to_string()),
+            DeathCause::Starvation => None,
+        }
+    }
+
+    fn message(&self, username: &str) -> String {
+        match self {
+            DeathCause::Player(killer) => format!("{} was killed by {}", username, killer),
+            DeathCause::Mob(name) => format!("{} was killed by a {}", username, name),
+            DeathCause::Starvation => format!("{} starved to death", username),
+        }
+    }
+}
+
+pub struct PlayerStatsUpdate {
+    pub id: Uuid,
+    pub health: i32,
+    pub hunger: i32,
+    pub health_changed: bool,
+}
+
+pub struct PlayerMove {
+    pub id: Uuid,
+    pub to: Vec2,
+}
+
+pub struct MobAttack {
+    pub mob: &'static str,
+    pub target: Uuid,
+    pub new_health: i32,
+}
+
+#[derive(Default)]
+pub struct TickUpdate {
+    pub moves: Vec<PlayerMove>,
+    pub rejected_moves: Vec<(Uuid, Vec2)>,
+    pub stats: Vec<PlayerStatsUpdate>,
+    pub spawned: Vec<Entity>,
+    pub mob_moves: Vec<EntityMovement>,
+    pub mob_attacks: Vec<MobAttack>,
+    pub left: Vec<LeftPlayer>,
+}
+
+struct Regrowth {
+    position: Vec2,
+    regrows_at: Instant,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct LifetimeStats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub resources_gathered: u32,
+}
+
+/// A player that was taken out of a game, along with the clients that could see them.
+pub struct LeftPlayer {
+    pub id: Uuid,
+    pub username: String,
+    pub viewers: Vec<Uuid>,
+}
+
+pub struct DeadPlayer {
+    pub username: String,
+    pub skin: i32,
+    pub died_at: Instant,
+    pub spectator_position: Vec2,
+}
+
+/// A game that is only on disk, because nobody played it for a while or since the server
+/// started. It is loaded from its save when someone opens it. Read from the head of a save.
+#[derive(Clone, Deserialize)]
+pub struct UnloadedGame {
     pub id: Uuid,
     pub name: String,
+    pub seed: u32,
+    #[serde(default)]
+    pub settings: GameSettings,
+    pub owner: Option<String>,
+    pub created_at: SystemTime,
+}
 
-    pub entity_map: HashMap<Uuid, Entity>,
+pub struct Game {
+    pub id: Uuid,
+    pub name: String,
+    pub seed: u32,
+    pub settings: GameSettings,
+    pub owner: Option<String>,
+    pub created_at: SystemTime,
+    idle_since: Option<Instant>,
+    removed: bool,
+
+    pub entity_map: EntityMap,
     usernames: HashSet<String>,
-    pub client_entity_view: HashMap<Uuid, HashSet<Uuid>>,
+    pub interest: InterestManager,
+    move_budgets: HashMap<Uuid, MoveBudget>,
+    pending_moves: HashMap<Uuid, Vec2>,
+    regrowths: Vec<Regrowth>,
+    mobs: HashMap<Uuid, Mob>,
+    ticks: u64,
+    pub dead_players: HashMap<Uuid, DeadPlayer>,
+    disconnected: HashMap<Uuid, Instant>,
+    resume_tokens: HashMap<String, Uuid>,
+    saved_players: HashMap<String, SavedPlayer>,
+    player_stats: HashMap<String, LifetimeStats>,
+    outboxes: HashMap<Uuid, Outbox>,
 
     terrain_generator: TerrainGenerator,
 }
@@ -173,42 +412,40 @@ pub struct Game {
 pub struct Client {
     pub id: Uuid,
     pub username: Option<String>,
-    pub ws_session: Arc<Mutex<actix_ws::Session>>,
     pub game: Option<Arc<Mutex<Game>>>,
+    game_input: Option<mpsc::Sender<GameInput>>,
+    pub outbox: Outbox,
 }
 
 #[derive(Default)]
 pub struct ServerState {
     pub games: HashMap<Uuid, Arc<Mutex<Game>>>,
+    game_loops: HashMap<Uuid, GameLoop>,
     pub game_ids_by_name: HashMap<String, Uuid>,
+    pub unloaded_games: HashMap<String, UnloadedGame>,
     pub clients: HashMap<Uuid, Client>,
+    pub recipes: Arc<RecipeRegistry>,
+    pub accounts: AccountStore,
+    pub config: ServerConfig,
+    pub outbox_metrics: Arc<OutboxMetrics>,
+    /// Held while a save is written or deleted
+    pub save_lock: Arc<Mutex<()>>,
 }
 
 pub type SharedState = Arc<Mutex<ServerState>>;
 
 // IMPL
 
-fn get_chunk_coords_visible_from(position: Vec2) -> Vec<(i32, i32)> {
-    let mut coords = vec![];
-
-    let chunk_size_f: f32 = CHUNK_SIZE as f32;
-
-    let start_y =
-        (((position.y - VIEW_RANGE - chunk_size_f / 2.0) / chunk_size_f).floor() as i32).max(0);
-    let end_y = (((position.y + VIEW_RANGE - chunk_size_f / 2.0) / chunk_size_f).ceil() as i32)
-        .min(WORLD_SIZE / CHUNK_SIZE);
-    let start_x =
-        (((position.x - VIEW_RANGE - chunk_size_f / 2.0) / chunk_size_f).floor() as i32).max(0);
-    let end_x = (((position.x + VIEW_RANGE - chunk_size_f / 2.0) / chunk_size_f).ceil() as i32)
-        .min(WORLD_SIZE / CHUNK_SIZE);
-
-    for y in start_y..=end_y {
-        for x in start_x..=end_x {
-            coords.push((x, y));
-        }
-    }
+fn get_tile_coords_of(position: Vec2) -> (i32, i32) {
+    (position.x.round() as i32, position.y.round() as i32)
+}
 
-    coords
+/// Where the player with the id is, if it is a living player.
+fn player_position(entity_map: &EntityMap, id: Uuid) -> Option<Vec2> {
+    entity_map
+        .get(&id)
+        .filter(|entity| matches!(entity.value, EntityType::Player(_)))
+        .map(|entity| entity.position)
 }
 
 impl Entity {
@@ -221,7 +458,7 @@ impl Entity {
         }
     }
 
-    pub fn player(id: Uuid, position: Vec2, username: String, skin: i32) -> Self {
+    pub fn player(id: Uuid, position: Vec2, username: String, skin: i32, health: i32) -> Self {
         Self::new(
             id,
             position,
@@ -230,38 +467,57 @@ impl Entity {
                 skin,
                 inventory: Inventory {
                     slots: vec![
-                        Some(Item::IronSword),
-                        Some(Item::IronPickaxe),
-                        Some(Item::IronAxe),
+                        Some(ItemStack::new(Item::IronSword, 1)),
+                        Some(ItemStack::new(Item::IronPickaxe, 1)),
+                        Some(ItemStack::new(Item::IronAxe, 1)),
                         None,
                         None,
                         None,
                     ],
                     selected: 0,
                 },
+                hunger: MAX_HUNGER,
             }),
-            MAX_PLAYER_HEALTH,
+            health,
+        )
+    }
+
+    fn to_saved_player(&self, player: &EntityPlayer) -> SavedPlayer {
+        SavedPlayer {
+            position: self.position,
+            health: self.health,
+            hunger: player.hunger,
+            inventory: player.inventory.clone(),
+        }
+    }
+
+    pub fn dropped_item(id: Uuid, position: Vec2, stack: ItemStack, health: i32) -> Self {
+        Self::new(
+            id,
+            position,
+            EntityType::DroppedItem(EntityDroppedItem { stack }),
+            health,
         )
     }
 }
 
 impl Game {
     pub fn log<S: AsRef<str>>(&self, message: S) {
-        println!("[G_{}] ({}) {}", self.id, self.name, message.as_ref());
+        info!("[G_{}] ({}) {}", self.id, self.name, message.as_ref());
     }
 
     pub fn elog<S: AsRef<str>>(&self, message: S) {
-        eprintln!("[G_{}] ({}) {}", self.id, self.name, message.as_ref());
+        error!("[G_{}] ({}) {}", self.id, self.name, message.as_ref());
     }
 
-    pub fn new(id: Uuid, name: String, seed: u32) -> Self {
-        let terrain_generator = TerrainGenerator::new(seed);
-        let mut entity_map = HashMap::new();
+    pub fn new(id: Uuid, name: String, seed: u32, settings: GameSettings) -> Self {
+        let terrain_generator = TerrainGenerator::new(seed, &settings);
+        let mut entity_map = EntityMap::new();
 
-        for x in 0..WORLD_SIZE {
-            for y in 0..WORLD_SIZE {
+        for x in 0..settings.world_size {
+            for y in 0..settings.world_size {
                 if let Some(ent) = terrain_generator.get_entity(x, y) {
-                    entity_map.insert(ent.id, ent);
+                    entity_map.insert(ent);
                 }
             }
         }
@@ -269,567 +525,2034 @@ impl Game {
         Self {
             id,
             name,
+            seed,
+            settings,
+            owner: None,
+            created_at: SystemTime::now(),
+            idle_since: None,
+            removed: false,
 
             entity_map,
             usernames: HashSet::new(),
-            client_entity_view: HashMap::new(),
+            interest: InterestManager::new(),
+            move_budgets: HashMap::new(),
+            pending_moves: HashMap::new(),
+            regrowths: vec![],
+            mobs: HashMap::new(),
+            ticks: 0,
+            dead_players: HashMap::new(),
+            disconnected: HashMap::new(),
+            resume_tokens: HashMap::new(),
+            saved_players: HashMap::new(),
+            player_stats: HashMap::new(),
+            outboxes: HashMap::new(),
 
             terrain_generator,
         }
     }
 
-    pub fn get_new_spawn_location(&self) -> Vec2 {
-        const NO_SPAWN_BORDER: i32 = WATER_EDGE_SIZE * 2;
+    /// Regenerates the world from the seed and applies the saved changes on top of it.
+    pub fn from_save(save: GameSave) -> Self {
+        let mut game = Self::new(save.id, save.name, save.seed, save.settings);
+        game.owner = save.owner;
+        game.created_at = save.created_at;
 
-        let mut rng = rand::rng();
+        let generated: HashMap<_, _> = game
+            .entity_map
+            .values()
+            .map(|entity| (get_tile_coords_of(entity.position), entity.id))
+            .collect();
 
-        loop {
-            let x = rng.random_range(NO_SPAWN_BORDER..WORLD_SIZE - NO_SPAWN_BORDER);
-            let y = rng.random_range(NO_SPAWN_BORDER..WORLD_SIZE - NO_SPAWN_BORDER);
+        let now = Instant::now();
 
-            match self.terrain_generator.get_tile(x as f64, y as f64) {
-                TileType::Water | TileType::DeepWater => continue,
-                _ => {}
+        for harvested in save.harvested {
+            if let Some(id) = generated.get(&get_tile_coords_of(harvested.position)) {
+                game.entity_map.remove(id);
             }
 
-            // TODO: if an entity is here, continue
+            game.regrowths.push(Regrowth {
+                position: harvested.position,
+                regrows_at: now + Duration::from_secs_f32(harvested.regrows_in.max(0.0)),
+            });
+        }
+
+        for damaged in save.damaged {
+            if let Some(id) = generated.get(&get_tile_coords_of(damaged.position))
+                && let Some(entity) = game.entity_map.get_mut(id)
+            {
+                entity.health = damaged.health;
+            }
+        }
 
-            return Vec2::new(x as f32, y as f32);
+        for entity in save.entities {
+            game.entity_map.insert(entity);
         }
+
+        game.saved_players = save.players;
+        game.player_stats = save.stats;
+
+        game
     }
 
-    pub fn get_chunk_data(&self, x: i32, y: i32) -> TerrainChunk {
-        let mut tiles = vec![];
+    /// Mobs are not saved, they spawn again. Players that are alive are saved by username and
+    /// continue where they were when they join again.
+    pub fn to_save(&self) -> GameSave {
+        let now = Instant::now();
 
-        for cy in 0..CHUNK_SIZE {
-            for cx in 0..CHUNK_SIZE {
-                let tile = self
-                    .terrain_generator
-                    .get_tile((CHUNK_SIZE * x + cx) as f64, (CHUNK_SIZE * y + cy) as f64);
+        let mut damaged = vec![];
+        let mut entities = vec![];
+        let mut players = self.saved_players.clone();
 
-                tiles.push(tile);
+        for entity in self.entity_map.values() {
+            match &entity.value {
+                EntityType::Player(player) => {
+                    players.insert(player.username.clone(), entity.to_saved_player(player));
+                }
+                EntityType::DroppedItem(_) => entities.push(entity.clone()),
+                value if value.is_mob() => {}
+                _ => {
+                    if entity.health < self.settings.max_world_entity_health {
+                        damaged.push(DamagedEntity {
+                            position: entity.position,
+                            health: entity.health,
+                        });
+                    }
+                }
             }
         }
 
-        TerrainChunk {
-            position: Vec2::new(x as f32, y as f32),
-            contents: tiles,
+        let harvested = self
+            .regrowths
+            .iter()
+            .map(|regrowth| HarvestedEntity {
+                position: regrowth.position,
+                regrows_in: regrowth
+                    .regrows_at
+                    .saturating_duration_since(now)
+                    .as_secs_f32(),
+            })
+            .collect();
+
+        GameSave {
+            id: self.id,
+            name: self.name.clone(),
+            seed: self.seed,
+            settings: self.settings.clone(),
+            owner: self.owner.clone(),
+            created_at: self.created_at,
+            harvested,
+            damaged,
+            entities,
+            players,
+            stats: self.player_stats.clone(),
         }
     }
 
-    pub fn add_entity_to_client_view(&mut self, client_id: Uuid, entity_id: Uuid) {
-        self.client_entity_view
-            .entry(client_id)
-            .or_default()
-            .insert(entity_id);
+    /// Hands out the state a player had when the game was last saved, once.
+    fn take_saved_player(&mut self, username: &str) -> Option<SavedPlayer> {
+        self.saved_players.remove(username)
     }
 
-    pub fn remove_entity_from_client_view(&mut self, client_id: Uuid, entity_id: &Uuid) {
-        if let Some(set) = self.client_entity_view.get_mut(&client_id) {
-            set.remove(entity_id);
-        }
-    }
+    /// Takes a player out of the game and frees their username. Living players are saved so
+    /// they can continue when they join again.
+    pub fn remove_player(&mut self, id: Uuid) -> Option<LeftPlayer> {
+        let username = if let Some(dead_player) = self.dead_players.remove(&id) {
+            dead_player.username
+        } else {
+            let entity = self.entity_map.get(&id)?;
+            let EntityType::Player(player) = &entity.value else {
+                return None;
+            };
+
+            let username = player.username.clone();
+            let saved_player = entity.to_saved_player(player);
+
+            self.entity_map.remove(&id);
+            self.saved_players.insert(username.clone(), saved_player);
+
+            username
+        };
+
+        self.usernames.remove(&username);
+        self.outboxes.remove(&id);
+        self.disconnected.remove(&id);
+        self.resume_tokens.retain(|_, player_id| *player_id != id);
+        self.move_budgets.remove(&id);
+        self.pending_moves.remove(&id);
+        self.interest.remove_client(id);
+        let viewers = self.interest.entity_removed(&id);
 
-    pub fn client_sees_entity(&self, client_id: Uuid, entity_id: &Uuid) -> bool {
-        self.client_entity_view
-            .get(&client_id)
-            .is_some_and(|set| set.contains(entity_id))
+        Some(LeftPlayer {
+            id,
+            username,
+            viewers,
+        })
     }
-}
 
-impl Client {
-    async fn get_player_entity(&self) -> Option<Entity> {
-        if let Some(game_arc) = &self.game {
-            let game = game_arc.lock().await;
-            game.entity_map.get(&self.id).cloned()
-        } else {
-            None
+    /// Keeps the player of a closed connection in the game for `DISCONNECT_GRACE_PERIOD`
+    /// seconds before removing them.
+    pub fn disconnect_player(&mut self, id: Uuid) {
+        self.outboxes.remove(&id);
+
+        if self.entity_map.contains_key(&id) || self.dead_players.contains_key(&id) {
+            self.disconnected.insert(id, Instant::now());
         }
     }
 
-    async fn get_player_entity_by_username(&self, username: &str) -> Option<Entity> {
-        if let Some(game_arc) = &self.game {
-            let game = game_arc.lock().await;
+    /// Counts living, dead and disconnected players that have not left yet.
+    pub fn player_count(&self) -> usize {
+        self.usernames.len()
+    }
 
-            for ent in game.entity_map.values() {
-                if let EntityType::Player(entity_player) = &ent.value
-                    && entity_player.username == username
-                {
-                    return Some(ent.clone());
-                }
-            }
+    /// Whether the game was deleted or unloaded, it must not be saved anymore then.
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
 
-            None
-        } else {
-            None
+    fn update_idle(&mut self) {
+        if self.player_count() > 0 {
+            self.idle_since = None;
+        } else if self.idle_since.is_none() {
+            self.idle_since = Some(Instant::now());
         }
     }
 
-    async fn get_player(&self) -> Option<EntityPlayer> {
-        if let EntityType::Player(player) = self
-            .get_player_entity()
-            .await
-            .map(|entity| entity.value.clone())?
-        {
-            return Some(player);
+    /// How long nobody played the game, if nobody plays it right now.
+    pub fn idle_for(&self) -> Option<Duration> {
+        if self.player_count() > 0 {
+            return None;
         }
 
-        panic!("Client entity is not a player");
+        self.idle_since.map(|idle_since| idle_since.elapsed())
     }
 
-    async fn log_prefix(&self) -> String {
-        match &self.get_player().await {
-            Some(EntityPlayer { username, .. }) => format!("[C_{}] ({})", self.id, username),
-            _ => format!("[C_{}]", self.id),
-        }
+    /// Starts counting how long nobody played from now on.
+    pub fn reset_idle(&mut self) {
+        self.idle_since = None;
     }
 
-    pub async fn log<S: AsRef<str>>(&self, message: S) {
-        println!("{} {}", self.log_prefix().await, message.as_ref());
+    fn username_of(&self, id: &Uuid) -> Option<&String> {
+        match self.entity_map.get(id).map(|entity| &entity.value) {
+            Some(EntityType::Player(player)) => Some(&player.username),
+            _ => self.dead_players.get(id).map(|dead| &dead.username),
+        }
     }
 
-    pub async fn elog<S: AsRef<str>>(&self, message: S) {
-        println!("{} {}", self.log_prefix().await, message.as_ref());
+    pub fn disconnected_player_named(&self, username: &str) -> Option<Uuid> {
+        self.disconnected
+            .keys()
+            .copied()
+            .find(|id| self.username_of(id).is_some_and(|name| name == username))
     }
 
-    pub async fn send(&self, packet: Packet) {
-        let json = serde_json::to_string(&packet).unwrap();
-        let mut session = self.ws_session.lock().await;
-        session.text(json).await.ok();
-    }
+    /// Replaces the player's resume token with a new one.
+    pub fn issue_resume_token(&mut self, id: Uuid) -> String {
+        self.resume_tokens.retain(|_, player_id| *player_id != id);
+
+        let token = Uuid::new_v4().simple().to_string();
+        self.resume_tokens.insert(token.clone(), id);
 
-    pub async fn send_error<S: AsRef<str>>(&self, error: S) {
-        let packet = ErrorPacket::new(error);
-        let json = serde_json::to_string(&packet).unwrap();
-        let mut session = self.ws_session.lock().await;
-        session.text(json).await.ok();
+        token
     }
 
-    pub async fn move_player(&self, new_position: Vec2, state: &mut ServerState, notify: bool) {
-        let game_arc = match &self.game {
-            Some(g) => g.clone(),
-            _ => return,
-        };
+    /// Hands a player back to a new connection, ending their grace period. Also works while
+    /// the old connection has not been noticed to be gone yet.
+    pub fn resume_player(&mut self, resume_token: &str) -> Option<(Uuid, String)> {
+        let id = *self.resume_tokens.get(resume_token)?;
+        let username = self.username_of(&id)?.clone();
 
-        let mut game_guard = game_arc.lock().await;
+        self.disconnected.remove(&id);
 
-        let prev_position = match game_guard.entity_map.get(&self.id) {
-            Some(e) => e.position,
-            _ => return,
-        };
+        Some((id, username))
+    }
 
-        if let Some(entity_mut) = game_guard.entity_map.get_mut(&self.id) {
-            entity_mut.position = new_position;
-        }
+    fn remove_expired_players(&mut self) -> Vec<LeftPlayer> {
+        let grace_period = Duration::from_secs_f32(DISCONNECT_GRACE_PERIOD);
 
-        let prev_chunks: HashSet<_> = get_chunk_coords_visible_from(prev_position)
-            .into_iter()
+        let expired: Vec<_> = self
+            .disconnected
+            .iter()
+            .filter(|(_, disconnected_at)| disconnected_at.elapsed() >= grace_period)
+            .map(|(id, _)| *id)
             .collect();
-        let new_chunks: Vec<_> = get_chunk_coords_visible_from(new_position)
+
+        expired
             .into_iter()
-            .filter(|c| !prev_chunks.contains(c))
-            .collect();
+            .filter_map(|id| self.remove_player(id))
+            .collect()
+    }
 
-        for (x, y) in new_chunks.clone() {
-            let chunk = game_guard.get_chunk_data(x, y);
+    pub fn stats_of(&self, username: &str) -> LifetimeStats {
+        self.player_stats.get(username).cloned().unwrap_or_default()
+    }
 
-            self.send(Packet::TerrainChunk { chunk }).await;
-        }
+    fn stats_of_mut(&mut self, username: &str) -> &mut LifetimeStats {
+        self.player_stats.entry(username.to_string()).or_default()
+    }
 
-        let mut newly_visible = Vec::new();
-        let mut no_longer_visible = Vec::new();
+    /// A random free spot on land away from the border, `None` if none was found in
+    /// `SPAWN_LOCATION_ATTEMPTS` tries.
+    pub fn get_new_spawn_location(&self) -> Option<Vec2> {
+        let border = self.settings.no_spawn_border();
+        let world_size = self.settings.world_size;
 
-        for entity in game_guard.entity_map.values() {
-            let entity_chunk = (
-                (entity.position.x / CHUNK_SIZE as f32).floor() as i32,
-                (entity.position.y / CHUNK_SIZE as f32).floor() as i32,
-            );
+        let mut rng = rand::rng();
 
-            if game_guard
-                .client_entity_view
-                .get(&self.id)
-                .is_some_and(|set| set.contains(&entity.id))
-            {
-                if (entity.position.x - new_position.x).hypot(entity.position.y - new_position.y)
-                    > 100.0
-                {
-                    no_longer_visible.push(entity.id);
-                }
-            } else if new_chunks.contains(&entity_chunk) {
-                newly_visible.push(entity.id);
+        for _ in 0..SPAWN_LOCATION_ATTEMPTS {
+            let x = rng.random_range(border..world_size - border);
+            let y = rng.random_range(border..world_size - border);
+
+            match self.terrain_generator.get_tile(x as f64, y as f64) {
+                TileType::Water | TileType::DeepWater => continue,
+                _ => {}
             }
-        }
 
-        for entity_id in newly_visible {
-            if let Some(entity) = game_guard.entity_map.get(&entity_id) {
-                self.send(Packet::EntityLoad {
-                    entity: entity.clone(),
-                })
-                .await;
-                game_guard.add_entity_to_client_view(self.id, entity_id);
+            let position = Vec2::new(x as f32, y as f32);
+            if self.entity_map.near(position, ENTITY_SIZE).any(|entity| {
+                entity.value.is_solid() && entity.position.distance(position) < ENTITY_SIZE
+            }) {
+                continue;
             }
+
+            return Some(position);
         }
 
-        for entity_id in no_longer_visible {
-            self.send(Packet::EntityUnload { id: entity_id }).await;
-            game_guard.remove_entity_from_client_view(self.id, &entity_id);
+        None
+    }
+
+    /// Where a player with the username spawns: where they were saved, or a new spawn location.
+    fn spawn_location_for(&self, username: &str) -> Option<Vec2> {
+        match self.saved_players.get(username) {
+            Some(saved) => Some(saved.position),
+            _ => self.get_new_spawn_location(),
         }
+    }
+
+    pub fn get_chunk_data(&self, x: i32, y: i32) -> TerrainChunk {
+        let mut tiles = vec![];
 
-        let all_clients: Vec<Client> = state.clients.values().cloned().collect();
+        for cy in 0..CHUNK_SIZE {
+            for cx in 0..CHUNK_SIZE {
+                let tile = self
+                    .terrain_generator
+                    .get_tile((CHUNK_SIZE * x + cx) as f64, (CHUNK_SIZE * y + cy) as f64);
 
-        for client in all_clients {
-            if !notify && client.id == self.id {
-                continue;
+                tiles.push(tile);
             }
+        }
 
-            client
-                .send(Packet::EntityMove {
-                    id: self.id,
-                    new_position,
-                })
-                .await;
+        TerrainChunk {
+            position: Vec2::new(x as f32, y as f32),
+            contents: tiles,
         }
     }
 
-    async fn handle_attack(&self, cursor: Vec2, state: &mut ServerState) {
-        let game_arc = match &self.game {
-            Some(g) => g.clone(),
-            _ => return,
-        };
+    /// Movement is paid for from an allowance refilled at the max speed, banking up to
+    /// `MAX_MOVE_BURST` seconds so packets bunched up by network jitter are still accepted.
+    pub fn validate_player_move(
+        &mut self,
+        id: Uuid,
+        from: Vec2,
+        to: Vec2,
+    ) -> Result<(), MoveError> {
+        const PATH_STEP: f32 = 0.5;
+        const COLLISION_EPSILON: f32 = 0.05;
+
+        let world_size = self.settings.world_size as f32;
+        if !(0.0..world_size).contains(&to.x) || !(0.0..world_size).contains(&to.y) {
+            return Err(MoveError::OutOfBounds);
+        }
 
-        let (target_id, new_health, target_was_alive, game_id, attacker, victim) = {
-            let mut game = game_arc.lock().await;
+        let now = Instant::now();
+        let refill_rate = MAX_PLAYER_SPEED * MOVE_SPEED_TOLERANCE;
+        let max_allowance = refill_rate * MAX_MOVE_BURST;
 
-            let attacker = if let Entity {
-                value: EntityType::Player(p),
-                ..
-            } = game.entity_map.get(&self.id).unwrap()
-            {
-                p.clone()
-            } else {
-                panic!("Attack has no attacker");
-            };
+        let budget = self.move_budgets.entry(id).or_insert(MoveBudget {
+            allowance: max_allowance,
+            last_update: now,
+        });
 
-            let mut hit: Option<Uuid> = None;
-            let mut best_dist = f32::MAX;
+        let elapsed = now.duration_since(budget.last_update).as_secs_f32();
+        budget.allowance = (budget.allowance + elapsed * refill_rate).min(max_allowance);
+        budget.last_update = now;
 
-            for (id, entity) in &game.entity_map {
-                if *id == self.id {
-                    continue;
-                }
-                let dist = (entity.position.x - cursor.x).hypot(entity.position.y - cursor.y);
-                if dist < ATTACK_RANGE && dist < best_dist {
-                    best_dist = dist;
-                    hit = Some(*id);
-                }
-            }
+        let distance = from.distance(to);
+        if distance > budget.allowance {
+            return Err(MoveError::TooFast);
+        }
 
-            let Some(target_id) = hit else { return };
+        let steps = (distance / PATH_STEP).ceil().max(1.0) as i32;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let x = from.x + (to.x - from.x) * t;
+            let y = from.y + (to.y - from.y) * t;
 
-            let target: &mut Entity = game.entity_map.get_mut(&target_id).unwrap();
-            target.health -= calculate_damage(target, attacker.inventory.hand_item());
-            let new_health = target.health;
-            let target_was_alive = new_health > 0;
+            let tile = self
+                .terrain_generator
+                .get_tile((x + 0.5).floor() as f64, (y + 0.5).floor() as f64);
 
-            let victim = if let Entity {
-                value: EntityType::Player(p),
-                ..
-            } = target.clone()
-            {
-                Some(p.clone())
-            } else {
-                None
-            };
+            if !tile.is_walkable() {
+                return Err(MoveError::Impassable);
+            }
+        }
 
-            (
-                target_id,
-                new_health,
-                target_was_alive,
-                game.id,
-                attacker,
-                victim,
-            )
+        let overlaps = |a: Vec2, b: Vec2| {
+            (a.x - b.x).abs() < ENTITY_SIZE - COLLISION_EPSILON
+                && (a.y - b.y).abs() < ENTITY_SIZE - COLLISION_EPSILON
         };
 
-        for client in state.clients.values() {
-            if let Some(client_game) = &client.game {
-                let cg = client_game.lock().await;
-                if cg.id == game_id && cg.client_sees_entity(client.id, &target_id) {
-                    client
-                        .send(Packet::EntityDamage {
-                            id: target_id,
-                            new_health,
-                        })
-                        .await;
-                }
+        // Players are allowed to walk out of something they are already stuck in
+        for entity in self.entity_map.near(to, ENTITY_SIZE) {
+            if entity.value.is_solid()
+                && overlaps(to, entity.position)
+                && !overlaps(from, entity.position)
+            {
+                return Err(MoveError::Collision);
             }
         }
 
-        if !target_was_alive {
-            let removed = {
-                let mut game = game_arc.lock().await;
-                game.entity_map.remove(&target_id)
-            };
+        if let Some(budget) = self.move_budgets.get_mut(&id) {
+            budget.allowance -= distance;
+        }
 
-            if removed.is_some() {
-                for client in state.clients.values() {
-                    if let Some(client_game) = &client.game {
-                        let cg = client_game.lock().await;
+        Ok(())
+    }
 
-                        if cg.client_sees_entity(client.id, &target_id) {
-                            client.send(Packet::EntityDeath { id: target_id }).await;
-                        }
+    /// Removes a killed entity from the world. Players are kept as spectators until they
+    /// respawn, which also keeps their username reserved.
+    pub fn remove_killed_entity(&mut self, id: Uuid) -> Option<Entity> {
+        let entity = self.entity_map.remove(&id)?;
+        self.mobs.remove(&id);
+
+        if let EntityType::Player(player) = &entity.value {
+            self.move_budgets.remove(&id);
+            self.dead_players.insert(
+                id,
+                DeadPlayer {
+                    username: player.username.clone(),
+                    skin: player.skin,
+                    died_at: Instant::now(),
+                    spectator_position: entity.position,
+                },
+            );
+        }
 
-                        if let Some(v) = victim.clone() {
-                            client
-                                .send(Packet::SystemMessage {
-                                    message: format!(
-                                        "{} was killed by {}",
-                                        v.username, attacker.username
-                                    ),
-                                })
-                                .await;
-                        }
-                    }
-                }
-            }
+        if let Some(delay) = entity.value.regrowth_delay() {
+            self.regrowths.push(Regrowth {
+                position: entity.position,
+                regrows_at: Instant::now() + delay,
+            });
         }
+
+        Some(entity)
     }
 
-    pub async fn recv(&mut self, packet: Packet, state: &mut ServerState) {
+    /// Puts harvested world entities whose regrowth delay is over back at their original
+    /// location. Regrowth is postponed while a player is standing there.
+    fn regrow_entities(&mut self) -> Vec<Entity> {
+        let now = Instant::now();
+        let mut regrown = vec![];
+
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.regrowths)
+            .into_iter()
+            .partition(|regrowth| regrowth.regrows_at <= now);
+        self.regrowths = pending;
+
+        for regrowth in due {
+            let occupied = self
+                .entity_map
+                .near(regrowth.position, ENTITY_SIZE)
+                .any(|entity| {
+                    matches!(entity.value, EntityType::Player(_))
+                        && (entity.position.x - regrowth.position.x).abs() < ENTITY_SIZE
+                        && (entity.position.y - regrowth.position.y).abs() < ENTITY_SIZE
+                });
+
+            if occupied {
+                self.regrowths.push(regrowth);
+                continue;
+            }
+
+            let (x, y) = get_tile_coords_of(regrowth.position);
+
+            if let Some(entity) = self.terrain_generator.get_entity(x, y) {
+                self.entity_map.insert(entity.clone());
+                regrown.push(entity);
+            }
+        }
+
+        regrown
+    }
+
+    fn spawn_mobs(&mut self) -> Vec<Entity> {
+        let mut rng = rand::rng();
+        let mut spawned = vec![];
+
+        for _ in 0..MOB_SPAWN_ATTEMPTS {
+            if self.mobs.len() >= MAX_MOBS {
+                break;
+            }
+
+            let x = rng.random_range(0..self.settings.world_size);
+            let y = rng.random_range(0..self.settings.world_size);
+
+            let Some(value) = mob_for_tile(self.terrain_generator.get_tile(x as f64, y as f64))
+            else {
+                continue;
+            };
+            let Some(stats) = value.mob_stats() else {
+                continue;
+            };
+
+            let position = Vec2::new(x as f32, y as f32);
+            if self
+                .entity_map
+                .near(position, MOB_SPAWN_MIN_PLAYER_DISTANCE)
+                .any(|entity| {
+                    matches!(entity.value, EntityType::Player(_))
+                        && entity.position.distance(position) < MOB_SPAWN_MIN_PLAYER_DISTANCE
+                })
+            {
+                continue;
+            }
+
+            let entity = Entity::new(Uuid::new_v4(), position, value, stats.max_health);
+            self.entity_map.insert(entity.clone());
+            self.mobs.insert(entity.id, Mob::default());
+            spawned.push(entity);
+        }
+
+        spawned
+    }
+
+    /// Makes an attacked mob fight back, or run away if it is passive.
+    pub fn provoke_mob(&mut self, mob_id: Uuid, attacker_id: Uuid) {
+        let Some(stats) = self
+            .entity_map
+            .get(&mob_id)
+            .and_then(|e| e.value.mob_stats())
+        else {
+            return;
+        };
+
+        if let Some(mob) = self.mobs.get_mut(&mob_id) {
+            mob.behaviour = match stats.temperament {
+                Temperament::Passive => Behaviour::Flee {
+                    from: attacker_id,
+                    until: Instant::now() + Duration::from_secs(5),
+                },
+                _ => Behaviour::Chase {
+                    target: attacker_id,
+                },
+            };
+        }
+    }
+
+    /// Moves every mob one tick along its current behaviour and lets mobs next to their
+    /// target attack it.
+    fn tick_mobs(&mut self) -> (Vec<EntityMovement>, Vec<MobAttack>) {
+        const WANDER_RANGE: f32 = 6.0;
+        const FLEE_DURATION: Duration = Duration::from_secs(3);
+
+        let dt = 1.0 / TICK_RATE as f32;
+        let now = Instant::now();
+        let mut rng = rand::rng();
+
+        let mut moves = vec![];
+        let mut attacks = vec![];
+
+        let mob_ids: Vec<Uuid> = self.mobs.keys().copied().collect();
+
+        for id in mob_ids {
+            let Some((position, stats)) = self
+                .entity_map
+                .get(&id)
+                .and_then(|entity| Some((entity.position, entity.value.mob_stats()?)))
+            else {
+                continue;
+            };
+            let Some(mob) = self.mobs.get_mut(&id) else {
+                continue;
+            };
+
+            let nearest_player = self
+                .entity_map
+                .near(position, stats.aggro_range)
+                .filter(|entity| matches!(entity.value, EntityType::Player(_)))
+                .map(|entity| (entity.id, entity.position.distance(position)))
+                .filter(|(_, distance)| *distance <= stats.aggro_range)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            mob.behaviour = match (mob.behaviour, nearest_player) {
+                (Behaviour::Chase { target }, _)
+                    if player_position(&self.entity_map, target)
+                        .is_some_and(|p| p.distance(position) <= stats.aggro_range * 2.0) =>
+                {
+                    Behaviour::Chase { target }
+                }
+                (Behaviour::Flee { from, until }, _)
+                    if until > now && player_position(&self.entity_map, from).is_some() =>
+                {
+                    Behaviour::Flee { from, until }
+                }
+                (_, Some((player_id, _))) if stats.temperament == Temperament::Hostile => {
+                    Behaviour::Chase { target: player_id }
+                }
+                (_, Some((player_id, _))) if stats.temperament == Temperament::Passive => {
+                    Behaviour::Flee {
+                        from: player_id,
+                        until: now + FLEE_DURATION,
+                    }
+                }
+                (Behaviour::Chase { .. } | Behaviour::Flee { .. }, _) => {
+                    Behaviour::Idle { until: now }
+                }
+                (behaviour, _) => behaviour,
+            };
+
+            let (destination, speed) = match mob.behaviour {
+                Behaviour::Idle { until } => {
+                    if until <= now {
+                        mob.behaviour = Behaviour::Wander {
+                            target: Vec2::new(
+                                position.x + rng.random_range(-WANDER_RANGE..WANDER_RANGE),
+                                position.y + rng.random_range(-WANDER_RANGE..WANDER_RANGE),
+                            ),
+                        };
+                    }
+                    continue;
+                }
+                Behaviour::Wander { target } => (target, stats.speed * 0.5),
+                Behaviour::Chase { target } => {
+                    let Some(target_position) = player_position(&self.entity_map, target) else {
+                        continue;
+                    };
+
+                    if target_position.distance(position) <= ATTACK_RANGE {
+                        let ready = mob.last_attack.is_none_or(|last_attack| {
+                            now.duration_since(last_attack).as_secs_f32() >= MOB_ATTACK_COOLDOWN
+                        });
+
+                        if ready && stats.damage > 0 {
+                            mob.last_attack = Some(now);
+                            attacks.push((stats.name, target, stats.damage));
+                        }
+                        continue;
+                    }
+
+                    (target_position, stats.speed)
+                }
+                Behaviour::Flee { from, .. } => {
+                    let Some(threat) = player_position(&self.entity_map, from) else {
+                        continue;
+                    };
+                    let away = Vec2::new(position.x - threat.x, position.y - threat.y);
+                    let length = away.x.hypot(away.y).max(0.01);
+
+                    (
+                        Vec2::new(
+                            position.x + away.x / length * WANDER_RANGE,
+                            position.y + away.y / length * WANDER_RANGE,
+                        ),
+                        stats.speed,
+                    )
+                }
+            };
+
+            let distance = position.distance(destination);
+            if distance < 0.1 {
+                mob.behaviour = Behaviour::Idle {
+                    until: now + Duration::from_secs_f32(rng.random_range(1.0..4.0)),
+                };
+                continue;
+            }
+
+            let step = (speed * dt).min(distance);
+            let dx = (destination.x - position.x) / distance * step;
+            let dy = (destination.y - position.y) / distance * step;
+
+            let world_size = self.settings.world_size as f32;
+            let is_walkable = |p: Vec2| {
+                (0.0..world_size).contains(&p.x)
+                    && (0.0..world_size).contains(&p.y)
+                    && self
+                        .terrain_generator
+                        .get_tile((p.x + 0.5).floor() as f64, (p.y + 0.5).floor() as f64)
+                        .is_walkable()
+            };
+
+            let new_position = [
+                Vec2::new(position.x + dx, position.y + dy),
+                Vec2::new(position.x + dx, position.y),
+                Vec2::new(position.x, position.y + dy),
+            ]
+            .into_iter()
+            .find(|candidate| is_walkable(*candidate));
+
+            let Some(new_position) = new_position else {
+                if let Behaviour::Wander { .. } = mob.behaviour {
+                    mob.behaviour = Behaviour::Idle { until: now };
+                }
+                continue;
+            };
+
+            self.entity_map.set_position(&id, new_position);
+
+            moves.push(EntityMovement { id, new_position });
+        }
+
+        let attacks = attacks
+            .into_iter()
+            .filter_map(|(mob, target, damage)| {
+                let entity = self.entity_map.get_mut(&target)?;
+                entity.health -= damage;
+
+                Some(MobAttack {
+                    mob,
+                    target,
+                    new_health: entity.health,
+                })
+            })
+            .collect();
+
+        (moves, attacks)
+    }
+
+    pub fn is_player_dead(&self, id: Uuid) -> bool {
+        self.dead_players.contains_key(&id)
+    }
+
+    /// Queues a move to be validated and applied on the next tick. Only the latest move of a
+    /// player within a tick is kept.
+    pub fn queue_move(&mut self, id: Uuid, to: Vec2) {
+        if self.entity_map.contains_key(&id) {
+            self.pending_moves.insert(id, to);
+        }
+    }
+
+    pub fn tick(&mut self) -> TickUpdate {
+        self.ticks += 1;
+
+        let mut update = TickUpdate::default();
+
+        for (id, to) in std::mem::take(&mut self.pending_moves) {
+            let Some(from) = self.entity_map.get(&id).map(|e| e.position) else {
+                continue;
+            };
+
+            match self.validate_player_move(id, from, to) {
+                Ok(()) => {
+                    self.entity_map.set_position(&id, to);
+
+                    update.moves.push(PlayerMove { id, to });
+                }
+                Err(error) => {
+                    self.client_dlog(&id, format!("Rejected move to {:?}: {:?}", to, error));
+                    update.rejected_moves.push((id, from));
+                }
+            }
+        }
+
+        let tick_rate = TICK_RATE as u64;
+        if self.ticks.is_multiple_of(tick_rate) {
+            let decay_hunger = (self.ticks / tick_rate).is_multiple_of(HUNGER_DECAY_SECONDS);
+            update.stats = self.tick_players(decay_hunger);
+            update.spawned = self.regrow_entities();
+            update.spawned.extend(self.spawn_mobs());
+            update.left = self.remove_expired_players();
+            self.update_idle();
+        }
+
+        (update.mob_moves, update.mob_attacks) = self.tick_mobs();
+
+        update
+    }
+
+    /// Advances hunger, health regeneration and starvation of every living player by one
+    /// second. Returns the players whose stats changed.
+    fn tick_players(&mut self, decay_hunger: bool) -> Vec<PlayerStatsUpdate> {
+        let mut updates = vec![];
+
+        for entity in self.entity_map.values_mut() {
+            let EntityType::Player(player) = &mut entity.value else {
+                continue;
+            };
+
+            let prev_health = entity.health;
+            let prev_hunger = player.hunger;
+
+            if decay_hunger {
+                player.hunger = (player.hunger - 1).max(0);
+            }
+
+            if player.hunger == 0 {
+                entity.health -= STARVATION_DAMAGE_PER_SECOND;
+            } else if player.hunger >= REGEN_MIN_HUNGER {
+                entity.health =
+                    (entity.health + HEALTH_REGEN_PER_SECOND).min(self.settings.max_player_health);
+            }
+
+            if entity.health != prev_health || player.hunger != prev_hunger {
+                updates.push(PlayerStatsUpdate {
+                    id: entity.id,
+                    health: entity.health,
+                    hunger: player.hunger,
+                    health_changed: entity.health != prev_health,
+                });
+            }
+        }
+
+        updates
+    }
+
+    pub fn spawn_loot(&mut self, entity: &Entity) -> Vec<Entity> {
+        const DROP_SPREAD: f32 = 0.5;
+
+        let mut rng = rand::rng();
+
+        entity
+            .value
+            .loot()
+            .into_iter()
+            .map(|stack| {
+                let position = Vec2::new(
+                    entity.position.x + rng.random_range(-DROP_SPREAD..DROP_SPREAD),
+                    entity.position.y + rng.random_range(-DROP_SPREAD..DROP_SPREAD),
+                );
+
+                let drop = Entity::dropped_item(
+                    Uuid::new_v4(),
+                    position,
+                    stack,
+                    self.settings.max_world_entity_health,
+                );
+                self.entity_map.insert(drop.clone());
+                drop
+            })
+            .collect()
+    }
+
+    pub fn pick_up_item(
+        &mut self,
+        player_id: Uuid,
+        item_id: Uuid,
+    ) -> Result<Inventory, PickupError> {
+        let item_entity = self.entity_map.get(&item_id).ok_or(PickupError::NotFound)?;
+
+        let EntityType::DroppedItem(EntityDroppedItem { stack }) = item_entity.value else {
+            return Err(PickupError::NotAnItem);
+        };
+        let item_position = item_entity.position;
+
+        let Some(Entity {
+            position,
+            value: EntityType::Player(player),
+            ..
+        }) = self.entity_map.get_mut(&player_id)
+        else {
+            return Err(PickupError::NotFound);
+        };
+
+        if position.distance(item_position) > PICKUP_RANGE {
+            return Err(PickupError::OutOfRange);
+        }
+
+        let leftover = player.inventory.add(stack);
+        if leftover == stack.count {
+            return Err(PickupError::InventoryFull);
+        }
+
+        let inventory = player.inventory.clone();
+
+        if leftover == 0 {
+            self.entity_map.remove(&item_id);
+        } else if let Some(Entity {
+            value: EntityType::DroppedItem(dropped),
+            ..
+        }) = self.entity_map.get_mut(&item_id)
+        {
+            dropped.stack.count = leftover;
+        }
+
+        Ok(inventory)
+    }
+
+    /// Moves the view of a client to the chunks visible from `position`.
+    pub fn update_client_view(&mut self, client_id: Uuid, position: Vec2) -> ViewChange {
+        let chunks = get_chunk_coords_visible_from(position, &self.settings);
+        self.interest.set_view(client_id, chunks, &self.entity_map)
+    }
+}
+
+impl Game {
+    fn client_log_prefix(&self, id: &Uuid) -> String {
+        match self.username_of(id) {
+            Some(username) => format!("[C_{}] ({})", id, username),
+            _ => format!("[C_{}]", id),
+        }
+    }
+
+    pub fn client_log<S: AsRef<str>>(&self, id: &Uuid, message: S) {
+        info!("{} {}", self.client_log_prefix(id), message.as_ref());
+    }
+
+    pub fn client_elog<S: AsRef<str>>(&self, id: &Uuid, message: S) {
+        warn!("{} {}", self.client_log_prefix(id), message.as_ref());
+    }
+
+    /// For client mistakes that happen all the time, like moves rejected because of lag.
+    pub fn client_dlog<S: AsRef<str>>(&self, id: &Uuid, message: S) {
+        debug!("{} {}", self.client_log_prefix(id), message.as_ref());
+    }
+
+    /// Sends the game's packets for a player to a connection, replacing the one it had.
+    pub fn connect(&mut self, id: Uuid, outbox: Outbox) {
+        self.outboxes.insert(id, outbox);
+    }
+
+    pub fn send_to(&self, id: &Uuid, packet: Packet) {
+        if let Some(outbox) = self.outboxes.get(id) {
+            outbox.send(packet);
+        }
+    }
+
+    /// Sends a packet to every connected player and spectator of the game.
+    pub fn broadcast(&self, packet: Packet) {
+        for outbox in self.outboxes.values() {
+            outbox.send(packet.clone());
+        }
+    }
+
+    /// Sends a packet to the clients that have loaded the entity.
+    pub fn send_to_viewers(&self, entity_id: &Uuid, packet: Packet) {
+        for viewer in self.interest.viewers_of(entity_id) {
+            self.send_to(&viewer, packet.clone());
+        }
+    }
+
+    fn send_inventory(&self, id: &Uuid, inventory: Inventory) {
+        self.send_to(
+            id,
+            Packet::InventoryState {
+                slots: inventory.slots,
+                selected: inventory.selected,
+            },
+        );
+    }
+
+    fn send_system_message<S: Into<String>>(&self, id: &Uuid, message: S) {
+        self.send_to(
+            id,
+            Packet::SystemMessage {
+                message: message.into(),
+            },
+        );
+    }
+
+    /// Handles a packet sent by a player of the game.
+    pub fn handle_packet(&mut self, id: Uuid, packet: Packet, recipes: &RecipeRegistry) {
         match packet {
-            Packet::PlayerRegister {
-                game_name,
-                username,
-                skin,
+            Packet::EntityMove {
+                id: entity_id,
+                new_position,
             } => {
-                if self.get_player().await.is_some() {
-                    self.elog("Tried to reregister").await;
+                if entity_id != id {
+                    return; // TODO: ponder
+                }
+
+                if self.is_player_dead(id) {
+                    if ALLOW_SPECTATING {
+                        self.move_spectator(id, new_position);
+                    }
+
                     return;
                 }
 
-                let game_id = *match state.game_ids_by_name.get(&game_name) {
-                    Some(id) => id,
-                    _ => {
-                        self.send_error("game-not-found").await;
-                        return;
-                    }
-                };
+                self.queue_move(id, new_position);
+            }
+
+            Packet::ChatMessageSend { message } => {
+                self.handle_chat(id, message, recipes);
+            }
+
+            Packet::PlayerAttack { cursor_world_pos } => {
+                self.handle_attack(id, cursor_world_pos);
+            }
+
+            Packet::ItemPickup { id: item_id } => {
+                self.handle_pickup(id, item_id);
+            }
+
+            Packet::Craft { recipe } => {
+                self.handle_craft(id, &recipe, recipes);
+            }
+
+            Packet::RecipeListRequest => {
+                self.send_recipe_list(id, recipes);
+            }
+
+            Packet::UseItem { slot } => {
+                self.handle_use_item(id, slot);
+            }
+
+            Packet::PlayerRespawn => {
+                self.handle_respawn(id, recipes);
+            }
+
+            Packet::InventoryMove { from, to } => {
+                self.update_inventory(id, |inventory| inventory.move_stack(from, to));
+            }
+
+            Packet::InventorySplit { from, to, count } => {
+                self.update_inventory(id, |inventory| inventory.split_stack(from, to, count));
+            }
+
+            Packet::InventorySelect { selected } => {
+                if let Some(Entity { value, .. }) = self.entity_map.get_mut(&id)
+                    && let EntityType::Player(player) = value
+                {
+                    player.inventory.selected = selected;
+                }
+            }
+
+            other => {
+                self.client_elog(&id, format!("Sent an unexpected packet: {:?}", other));
+            }
+        }
+    }
+
+    fn handle_chat(&mut self, id: Uuid, message: String, recipes: &RecipeRegistry) {
+        if message.starts_with('/') {
+            let p = message.split(' ').collect::<Vec<&str>>();
+            match &p[..] {
+                ["/tp", username] => {
+                    // Teleporting skips the move checks, so only the game's owner may do it
+                    if self.owner.is_none() || self.username_of(&id) != self.owner.as_ref() {
+                        self.send_system_message(&id, "Only the owner of the game can teleport");
+                        return;
+                    }
+
+                    let target_position = self
+                        .entity_map
+                        .values()
+                        .find(|entity| {
+                            matches!(&entity.value, EntityType::Player(player) if player.username == *username)
+                        })
+                        .map(|entity| entity.position);
+
+                    match target_position {
+                        Some(position) => self.move_player(id, position, true),
+                        None => {
+                            self.send_system_message(&id, format!("Player {} not found", username))
+                        }
+                    }
+                }
+                ["/recipes"] => {
+                    let Some(Entity {
+                        value: EntityType::Player(player),
+                        ..
+                    }) = self.entity_map.get(&id)
+                    else {
+                        return;
+                    };
+
+                    let craftable: Vec<_> = recipes
+                        .recipes()
+                        .iter()
+                        .filter(|recipe| recipe.can_craft(&player.inventory))
+                        .map(|recipe| recipe.name.as_str())
+                        .collect();
+
+                    let message = if craftable.is_empty() {
+                        "You cannot craft anything yet".to_string()
+                    } else {
+                        format!("You can craft: {}", craftable.join(", "))
+                    };
+
+                    self.send_system_message(&id, message);
+                }
+                ["/stats"] => {
+                    let Some(username) = self.username_of(&id) else {
+                        return;
+                    };
+
+                    let stats = self.stats_of(username);
+
+                    self.send_system_message(
+                        &id,
+                        format!(
+                            "Kills: {}, deaths: {}, resources gathered: {}",
+                            stats.kills, stats.deaths, stats.resources_gathered
+                        ),
+                    );
+                }
+                ["/craft", recipe_name] => {
+                    self.handle_craft(id, recipe_name, recipes);
+                }
+                _ => {
+                    self.send_system_message(&id, "Invalid command");
+                }
+            }
+
+            return;
+        }
+
+        let Some(username) = self.username_of(&id).cloned() else {
+            return;
+        };
+
+        self.broadcast(Packet::ChatMessage {
+            id: Uuid::new_v4(),
+            message,
+            username,
+        });
+    }
+
+    pub fn move_player(&mut self, id: Uuid, new_position: Vec2, notify: bool) {
+        if !self.entity_map.contains_key(&id) {
+            return;
+        }
+
+        self.entity_map.set_position(&id, new_position);
+
+        self.update_view(id, new_position);
+
+        let mut recipients = self.announce_move(id, new_position);
+        recipients.retain(|viewer| *viewer != id);
+        if notify {
+            recipients.push(id);
+        }
+
+        for recipient in recipients {
+            self.send_to(&recipient, Packet::EntityMove { id, new_position });
+        }
+    }
+
+    fn move_spectator(&mut self, id: Uuid, new_position: Vec2) {
+        let world_size = self.settings.world_size as f32;
+        let new_position = Vec2::new(
+            new_position.x.clamp(0.0, world_size),
+            new_position.y.clamp(0.0, world_size),
+        );
+
+        match self.dead_players.get_mut(&id) {
+            Some(dead_player) => dead_player.spectator_position = new_position,
+            _ => return,
+        }
+
+        self.update_view(id, new_position);
+    }
+
+    /// Moves the view of a client to `position`, sending it the chunks and entities that came
+    /// into view and unloading the ones that went out of view.
+    fn update_view(&mut self, id: Uuid, position: Vec2) {
+        let change = self.update_client_view(id, position);
+        self.send_view_change(&id, change);
+    }
+
+    fn send_view_change(&self, id: &Uuid, change: ViewChange) {
+        for entity_id in change.unloaded_entities {
+            self.send_to(id, Packet::EntityUnload { id: entity_id });
+        }
+
+        if !change.unloaded_chunks.is_empty() {
+            let chunks = change
+                .unloaded_chunks
+                .iter()
+                .map(|(x, y)| Vec2::new(*x as f32, *y as f32))
+                .collect();
+
+            self.send_to(id, Packet::TerrainChunkUnload { chunks });
+        }
+
+        if !change.loaded_chunks.is_empty() {
+            let chunks = change
+                .loaded_chunks
+                .iter()
+                .map(|(x, y)| self.get_chunk_data(*x, *y))
+                .collect();
+
+            self.send_to(id, Packet::TerrainChunkBatch { chunks });
+        }
+
+        for entity_id in change.loaded_entities {
+            if let Some(entity) = self.entity_map.get(&entity_id) {
+                self.send_to(
+                    id,
+                    Packet::EntityLoad {
+                        entity: entity.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Loads and unloads an entity that moved for the clients whose view it entered or left.
+    /// Returns the clients that still see it, which need the move itself.
+    fn announce_move(&mut self, id: Uuid, position: Vec2) -> Vec<Uuid> {
+        let interest = self.interest.entity_moved(id, position);
+
+        if let Some(entity) = self.entity_map.get(&id) {
+            for viewer in &interest.loaded {
+                self.send_to(
+                    viewer,
+                    Packet::EntityLoad {
+                        entity: entity.clone(),
+                    },
+                );
+            }
+        }
+
+        for viewer in &interest.unloaded {
+            self.send_to(viewer, Packet::EntityUnload { id });
+        }
+
+        interest.viewers
+    }
+
+    /// Unloads a player that left for everyone who could see them and tells the game.
+    pub fn announce_left(&self, left: LeftPlayer) {
+        for viewer in &left.viewers {
+            self.send_to(viewer, Packet::EntityUnload { id: left.id });
+        }
+
+        self.broadcast(Packet::SystemMessage {
+            message: format!("{} left the game", left.username),
+        });
+    }
+
+    fn handle_attack(&mut self, id: Uuid, cursor: Vec2) {
+        let (attacker, attacker_position) = match self.entity_map.get(&id) {
+            Some(Entity {
+                value: EntityType::Player(p),
+                position,
+                ..
+            }) => (p.clone(), *position),
+            _ => return,
+        };
+
+        // The cursor has to be within reach, like moves are checked against the player's speed
+        if attacker_position.distance(cursor) > ATTACK_RANGE + ENTITY_SIZE {
+            self.client_dlog(&id, format!("Attack at {:?} out of reach", cursor));
+            return;
+        }
+
+        let mut hit: Option<Uuid> = None;
+        let mut best_dist = f32::MAX;
+
+        for entity in self.entity_map.near(cursor, ATTACK_RANGE) {
+            if entity.id == id || !entity.value.is_attackable() {
+                continue;
+            }
+            if !self.settings.pvp && matches!(entity.value, EntityType::Player(_)) {
+                continue;
+            }
+            let dist = (entity.position.x - cursor.x).hypot(entity.position.y - cursor.y);
+            if dist < ATTACK_RANGE && dist < best_dist {
+                best_dist = dist;
+                hit = Some(entity.id);
+            }
+        }
+
+        let Some(target_id) = hit else { return };
+
+        let hand_item = attacker.inventory.hand_item();
+
+        let target: &mut Entity = self.entity_map.get_mut(&target_id).unwrap();
+        target.health -= calculate_damage(target, hand_item);
+        let new_health = target.health;
+
+        self.provoke_mob(target_id, id);
+
+        let worn_inventory = match self.entity_map.get_mut(&id) {
+            Some(Entity {
+                value: EntityType::Player(player),
+                ..
+            }) if hand_item.is_some_and(|item| item.max_durability().is_some()) => {
+                let broken_tool = player.inventory.wear_hand_item();
+                Some((player.inventory.clone(), broken_tool))
+            }
+            _ => None,
+        };
+
+        if let Some((inventory, broken_tool)) = worn_inventory {
+            self.send_inventory(&id, inventory);
+
+            if broken_tool.is_some() {
+                self.send_system_message(&id, "Your tool broke");
+            }
+        }
+
+        self.send_to_viewers(
+            &target_id,
+            Packet::EntityDamage {
+                id: target_id,
+                new_health,
+            },
+        );
+
+        if new_health <= 0 {
+            self.kill_entity(target_id, DeathCause::Player(attacker.username));
+        }
+    }
+
+    /// Removes a dead entity, drops its loot and tells everyone who could see it. Killed
+    /// players are also sent to the death screen.
+    pub fn kill_entity(&mut self, id: Uuid, cause: DeathCause) {
+        let Some(removed) = self.remove_killed_entity(id) else {
+            return;
+        };
+        let drops = self.spawn_loot(&removed);
+
+        if let EntityType::Player(player) = &removed.value {
+            self.stats_of_mut(&player.username).deaths += 1;
+        }
+
+        if let DeathCause::Player(killer) = &cause {
+            let gathered: u32 = drops
+                .iter()
+                .filter_map(|drop| match &drop.value {
+                    EntityType::DroppedItem(dropped) => Some(dropped.stack.count),
+                    _ => None,
+                })
+                .sum();
+
+            let stats = self.stats_of_mut(killer);
+
+            match &removed.value {
+                EntityType::Player(_) => stats.kills += 1,
+                value if value.is_mob() => stats.kills += 1,
+                _ => stats.resources_gathered += gathered,
+            }
+        }
+
+        let victim = match &removed.value {
+            EntityType::Player(player) => Some(player.username.clone()),
+            _ => None,
+        };
+
+        for viewer in self.interest.entity_removed(&id) {
+            self.send_to(&viewer, Packet::EntityDeath { id });
+        }
+
+        if let Some(username) = &victim {
+            self.broadcast(Packet::SystemMessage {
+                message: cause.message(username),
+            });
+        }
+
+        for drop in &drops {
+            for viewer in self.interest.entity_added(drop) {
+                self.send_to(
+                    &viewer,
+                    Packet::EntityLoad {
+                        entity: drop.clone(),
+                    },
+                );
+            }
+        }
+
+        if victim.is_some() {
+            self.send_to(
+                &id,
+                Packet::PlayerDeath {
+                    killer: cause.killer(),
+                    respawn_cooldown: RESPAWN_COOLDOWN,
+                    can_spectate: ALLOW_SPECTATING,
+                },
+            );
+        }
+    }
+
+    fn handle_respawn(&mut self, id: Uuid, recipes: &RecipeRegistry) {
+        let remaining = match self.dead_players.get(&id) {
+            Some(dead_player) => RESPAWN_COOLDOWN - dead_player.died_at.elapsed().as_secs_f32(),
+            None => {
+                self.client_elog(&id, "Tried to respawn while alive");
+                return;
+            }
+        };
+
+        if remaining > 0.0 {
+            self.send_system_message(
+                &id,
+                format!("You can respawn in {} seconds", remaining.ceil()),
+            );
+            return;
+        }
+
+        let Some(position) = self
+            .dead_players
+            .get(&id)
+            .and_then(|dead_player| self.spawn_location_for(&dead_player.username))
+        else {
+            self.send_system_message(&id, "There is no free spawn location, try again");
+            return;
+        };
+
+        let Some(dead_player) = self.dead_players.remove(&id) else {
+            return;
+        };
+
+        self.spawn_player(id, dead_player.username, dead_player.skin, position);
+
+        self.send_to(&id, Packet::PlayerRespawned);
+
+        self.send_recipe_list(id, recipes);
+
+        self.client_log(&id, "Respawned");
+    }
+
+    fn handle_pickup(&mut self, id: Uuid, item_id: Uuid) {
+        let inventory = match self.pick_up_item(id, item_id) {
+            Ok(inventory) => inventory,
+            Err(PickupError::InventoryFull) => {
+                self.send_system_message(&id, "Your inventory is full");
+                return;
+            }
+            Err(error) => {
+                self.client_elog(&id, format!("Failed to pick up {}: {:?}", item_id, error));
+                return;
+            }
+        };
 
-                let game = state.games.get(&game_id).unwrap().clone();
-                self.game = Some(game.clone());
-                let mut game_guard = game.lock().await;
+        self.send_inventory(&id, inventory);
 
-                if game_guard.usernames.contains(&username) {
-                    self.send_error("username-taken").await;
-                    return;
+        match self.entity_map.get(&item_id).cloned() {
+            Some(entity) => self.send_to_viewers(&item_id, Packet::EntityLoad { entity }),
+            None => {
+                for viewer in self.interest.entity_removed(&item_id) {
+                    self.send_to(&viewer, Packet::EntityUnload { id: item_id });
                 }
+            }
+        }
+    }
+
+    fn update_inventory(
+        &mut self,
+        id: Uuid,
+        update: impl FnOnce(&mut Inventory) -> Result<(), InventoryError>,
+    ) {
+        let Some(Entity {
+            value: EntityType::Player(player),
+            ..
+        }) = self.entity_map.get_mut(&id)
+        else {
+            return;
+        };
 
-                self.username = Some(username.clone());
+        let result = update(&mut player.inventory);
+        let inventory = player.inventory.clone();
 
-                game_guard.usernames.insert(username.clone());
+        if let Err(error) = result {
+            self.client_elog(&id, format!("Invalid inventory update: {:?}", error));
+        }
 
-                state.clients.insert(self.id, self.clone());
+        self.send_inventory(&id, inventory);
+    }
 
-                let position = game_guard.get_new_spawn_location();
+    fn handle_use_item(&mut self, id: Uuid, slot: i32) {
+        let Some(Entity {
+            value: EntityType::Player(player),
+            health,
+            ..
+        }) = self.entity_map.get_mut(&id)
+        else {
+            return;
+        };
 
-                let entity = Entity::player(self.id, position, username, skin);
+        let food_value = player
+            .inventory
+            .slots
+            .get(slot as usize)
+            .copied()
+            .flatten()
+            .and_then(|stack| stack.item.food_value());
+
+        let result = match food_value {
+            None => Err("You cannot eat that"),
+            Some(_) if player.hunger >= MAX_HUNGER => Err("You are not hungry"),
+            Some(food_value) => match player.inventory.take_one(slot) {
+                Ok(_) => {
+                    player.hunger = (player.hunger + food_value).min(MAX_HUNGER);
+                    Ok((player.inventory.clone(), *health, player.hunger))
+                }
+                Err(_) => Err("You cannot eat that"),
+            },
+        };
 
-                game_guard.entity_map.insert(self.id, entity.clone());
+        match result {
+            Ok((inventory, health, hunger)) => {
+                self.send_inventory(&id, inventory);
+                self.send_to(&id, Packet::PlayerStats { health, hunger });
+            }
+            Err(message) => {
+                self.send_system_message(&id, message);
+            }
+        }
+    }
 
-                let chunk_coords = get_chunk_coords_visible_from(position);
+    pub fn send_recipe_list(&self, id: Uuid, recipes: &RecipeRegistry) {
+        let Some(Entity {
+            value: EntityType::Player(player),
+            ..
+        }) = self.entity_map.get(&id)
+        else {
+            return;
+        };
 
-                let chunk_data: Vec<_> = chunk_coords
-                    .into_iter()
-                    .map(|(x, y)| game_guard.get_chunk_data(x, y))
-                    .collect();
+        let recipes = recipes
+            .recipes()
+            .iter()
+            .map(|recipe| AvailableRecipe {
+                craftable: recipe.can_craft(&player.inventory),
+                recipe: recipe.clone(),
+            })
+            .collect();
 
-                for chunk in chunk_data {
-                    let packet = Packet::TerrainChunk { chunk };
-                    self.send(packet).await;
-                }
+        self.send_to(&id, Packet::RecipeList { recipes });
+    }
 
-                let player_ids: Vec<_> = game_guard
-                    .entity_map
-                    .values()
-                    .filter_map(|e| match e.value {
-                        EntityType::Player(_) => Some(e.id),
-                        _ => None,
-                    })
-                    .collect();
+    fn handle_craft(&mut self, id: Uuid, recipe_name: &str, recipes: &RecipeRegistry) {
+        let Some(Entity {
+            value: EntityType::Player(player),
+            ..
+        }) = self.entity_map.get_mut(&id)
+        else {
+            return;
+        };
 
-                let entities_to_load = {
-                    let mut entities_to_load = vec![];
+        let result = recipes
+            .craft(recipe_name, &mut player.inventory)
+            .map(|()| player.inventory.clone());
 
-                    let visible_chunks: HashSet<_> = get_chunk_coords_visible_from(position)
-                        .into_iter()
-                        .collect();
+        match result {
+            Ok(inventory) => {
+                self.send_inventory(&id, inventory);
 
-                    for other_entity in game_guard.entity_map.values() {
-                        let entity_chunk = (
-                            (other_entity.position.x / CHUNK_SIZE as f32).floor() as i32,
-                            (other_entity.position.y / CHUNK_SIZE as f32).floor() as i32,
-                        );
+                self.send_recipe_list(id, recipes);
 
-                        if visible_chunks.contains(&entity_chunk)
-                            && !game_guard.client_sees_entity(self.id, &other_entity.id)
-                        {
-                            entities_to_load.push(other_entity.clone());
-                        }
+                self.client_log(&id, format!("Crafted {}", recipe_name));
+            }
+            Err(error) => {
+                let message = match error {
+                    CraftError::UnknownRecipe => format!("Unknown recipe {}", recipe_name),
+                    CraftError::MissingIngredients => {
+                        format!("Missing ingredients for {}", recipe_name)
                     }
-
-                    entities_to_load
+                    CraftError::InventoryFull => "Your inventory is full".to_string(),
                 };
 
-                for entity in entities_to_load {
-                    self.send(Packet::EntityLoad {
-                        entity: entity.clone(),
-                    })
-                    .await;
+                self.send_system_message(&id, message);
+            }
+        }
+    }
 
-                    game_guard.add_entity_to_client_view(self.id, entity.id);
-                }
+    /// Puts a fresh player entity at `position` into the game, sends its client everything
+    /// visible from there and loads it for everyone who can see it.
+    pub fn spawn_player(&mut self, id: Uuid, username: String, skin: i32, position: Vec2) {
+        let saved_player = self.take_saved_player(&username);
 
-                drop(game_guard);
+        let max_health = self.settings.max_player_health;
+        let mut entity = Entity::player(id, position, username, skin, max_health);
 
-                self.move_player(position, state, false).await;
+        if let Some(saved) = saved_player {
+            entity.health = saved.health;
 
-                let mut game_clients: Vec<Client> = Vec::new();
+            if let EntityType::Player(player) = &mut entity.value {
+                player.hunger = saved.hunger;
+                player.inventory = saved.inventory;
+            }
+        }
 
-                for id in player_ids {
-                    if let Some(client) = state.clients.get(&id)
-                        && let Some(client_game) = &client.game
-                        && client_game.lock().await.id == game_id
-                    {
-                        game_clients.push(client.clone());
-                    }
-                }
+        self.entity_map.insert(entity.clone());
 
-                for client in &game_clients {
-                    client
-                        .send(Packet::EntityLoad {
-                            entity: entity.clone(),
-                        })
-                        .await;
+        for viewer in self.interest.entity_added(&entity) {
+            self.send_to(
+                &viewer,
+                Packet::EntityLoad {
+                    entity: entity.clone(),
+                },
+            );
+        }
+
+        self.update_view(id, position);
+    }
 
-                    if client.id != self.id {
-                        let entity = client.get_player_entity().await.unwrap();
+    /// Runs a single tick and sends its outcome to the players.
+    pub fn run_tick(&mut self) {
+        let update = self.tick();
 
-                        self.send(Packet::EntityLoad { entity }).await;
-                    }
-                }
+        for PlayerMove { id, to } in &update.moves {
+            self.update_view(*id, *to);
+        }
 
-                self.send(Packet::PlayerRegistered { id: self.id }).await;
+        let mut moves_by_client: HashMap<Uuid, Vec<EntityMovement>> = HashMap::new();
 
-                let new_player_message =
-                    format!("{} joined the game", self.username.clone().unwrap());
-                for client in &game_clients {
-                    client
-                        .send(Packet::SystemMessage {
-                            message: new_player_message.clone(),
-                        })
-                        .await;
+        let player_moves = update.moves.iter().map(|player_move| EntityMovement {
+            id: player_move.id,
+            new_position: player_move.to,
+        });
+
+        for movement in player_moves.chain(update.mob_moves.iter().cloned()) {
+            for viewer in self.announce_move(movement.id, movement.new_position) {
+                if viewer != movement.id {
+                    moves_by_client
+                        .entry(viewer)
+                        .or_default()
+                        .push(movement.clone());
                 }
+            }
+        }
 
-                self.log("Registered").await;
+        for entity in &update.spawned {
+            for viewer in self.interest.entity_added(entity) {
+                self.send_to(
+                    &viewer,
+                    Packet::EntityLoad {
+                        entity: entity.clone(),
+                    },
+                );
             }
+        }
 
-            Packet::EntityMove { id, new_position } => {
-                if id != self.id {
-                    return; // TODO: ponder
-                }
+        for (id, position) in &update.rejected_moves {
+            self.send_to(
+                id,
+                Packet::EntityMove {
+                    id: *id,
+                    new_position: *position,
+                },
+            );
+        }
+
+        for (id, moves) in moves_by_client {
+            self.send_to(&id, Packet::EntityMoveBatch { moves });
+        }
+
+        for attack in &update.mob_attacks {
+            self.send_to_viewers(
+                &attack.target,
+                Packet::EntityDamage {
+                    id: attack.target,
+                    new_health: attack.new_health,
+                },
+            );
 
-                self.move_player(new_position, state, false).await;
+            if attack.new_health <= 0 {
+                self.kill_entity(attack.target, DeathCause::Mob(attack.mob));
             }
+        }
 
-            Packet::ChatMessageSend { message } => {
-                let all_clients: Vec<Client> = state.clients.values().cloned().collect();
-
-                if message.starts_with('/') {
-                    let p = message.split(' ').collect::<Vec<&str>>();
-                    match &p[..] {
-                        ["/tp", username] => {
-                            let target_position = {
-                                if let Some(target_entity) =
-                                    self.get_player_entity_by_username(username).await
-                                {
-                                    target_entity.position
-                                } else {
-                                    self.send(Packet::SystemMessage {
-                                        message: format!("Player {} not found", username),
-                                    })
-                                    .await;
-                                    return;
-                                }
-                            };
-
-                            self.move_player(target_position, state, true).await;
-
-                            return;
-                        }
-                        _ => {
-                            self.send(Packet::SystemMessage {
-                                message: "Invalid command".to_string(),
-                            })
-                            .await;
+        for left in update.left {
+            self.log(format!("{} left", left.username));
+            self.announce_left(left);
+        }
 
-                            return;
-                        }
-                    }
-                }
+        for update in update.stats {
+            self.send_to(
+                &update.id,
+                Packet::PlayerStats {
+                    health: update.health,
+                    hunger: update.hunger,
+                },
+            );
 
-                let message_id = Uuid::new_v4();
-                for client in &all_clients {
-                    let sender_name = self.username.clone().unwrap();
-                    client
-                        .send(Packet::ChatMessage {
-                            id: message_id,
-                            message: message.clone(),
-                            username: sender_name,
-                        })
-                        .await;
-                }
+            if update.health_changed {
+                self.send_to_viewers(
+                    &update.id,
+                    Packet::EntityDamage {
+                        id: update.id,
+                        new_health: update.health,
+                    },
+                );
             }
 
-            Packet::PlayerAttack { cursor_world_pos } => {
-                self.handle_attack(cursor_world_pos, state).await;
+            if update.health <= 0 {
+                self.kill_entity(update.id, DeathCause::Starvation);
             }
+        }
+    }
+}
 
-            Packet::InventorySelect { selected } => {
-                let game_arc = match &self.game {
-                    Some(g) => g.clone(),
-                    _ => return,
-                };
+impl Client {
+    pub fn new(id: Uuid, outbox: Outbox) -> Self {
+        Self {
+            id,
+            username: None,
+            game: None,
+            game_input: None,
+            outbox,
+        }
+    }
 
-                let mut game = game_arc.lock().await;
+    fn log_prefix(&self) -> String {
+        match &self.username {
+            Some(username) => format!("[C_{}] ({})", self.id, username),
+            _ => format!("[C_{}]", self.id),
+        }
+    }
 
-                if let Some(Entity { value, .. }) = game.entity_map.get_mut(&self.id)
-                    && let EntityType::Player(player) = value
-                {
-                    player.inventory.selected = selected;
+    pub fn log<S: AsRef<str>>(&self, message: S) {
+        info!("{} {}", self.log_prefix(), message.as_ref());
+    }
+
+    pub fn elog<S: AsRef<str>>(&self, message: S) {
+        warn!("{} {}", self.log_prefix(), message.as_ref());
+    }
+
+    pub fn send(&self, packet: Packet) {
+        self.outbox.send(packet);
+    }
+
+    pub fn send_error<S: AsRef<str>>(&self, error: S) {
+        self.outbox.send_error(error);
+    }
+
+    /// Handles a packet of this connection. Joining and resuming lock the server state, every
+    /// other packet is handed to the loop of the client's game.
+    pub async fn recv(&mut self, packet: Packet, state: &SharedState) {
+        match packet {
+            Packet::PlayerRegister {
+                game_name,
+                token,
+                skin,
+                fresh_start,
+            } => {
+                let mut state = state.lock().await;
+                self.refresh(&state);
+                self.handle_register(&game_name, &token, skin, fresh_start, &mut state)
+                    .await;
+            }
+
+            Packet::PlayerResume { resume_token } => {
+                let mut state = state.lock().await;
+                self.refresh(&state);
+                self.handle_resume(&resume_token, &mut state).await;
+            }
+
+            packet => match &self.game_input {
+                Some(sender) => {
+                    let input = GameInput {
+                        client_id: self.id,
+                        packet,
+                    };
+
+                    // Closed once the game is gone, the client is sent back to the menu then
+                    if let Err(mpsc::error::TrySendError::Full(_)) = sender.try_send(input)
+                        && !self.outbox.is_evicted()
+                    {
+                        self.elog("Sent game input faster than its game handles it");
+                        self.outbox.evict();
+                    }
                 }
+                None => self.elog(format!("Sent an unexpected packet: {:?}", packet)),
+            },
+        }
+    }
+
+    /// Picks up what happened to this client in the server state, like its game being removed.
+    fn refresh(&mut self, state: &ServerState) {
+        if let Some(client) = state.clients.get(&self.id) {
+            self.clone_from(client);
+        }
+    }
+
+    async fn handle_register(
+        &mut self,
+        game_name: &str,
+        token: &str,
+        skin: i32,
+        fresh_start: bool,
+        state: &mut ServerState,
+    ) {
+        if self.username.is_some() {
+            self.elog("Tried to reregister");
+            return;
+        }
+
+        let username = match state.accounts.username_for_token(token) {
+            Some(username) => username.to_string(),
+            _ => {
+                self.send_error("invalid-session");
+                return;
+            }
+        };
+
+        let game_id = *match state.game_ids_by_name.get(game_name) {
+            Some(id) => id,
+            _ => {
+                self.send_error("game-not-found");
+                return;
             }
+        };
 
-            other => {
-                self.elog(format!("Sent an unexpected packet: {:?}", other))
-                    .await;
+        let game_arc = state.games.get(&game_id).unwrap().clone();
+        let mut game = game_arc.lock().await;
+
+        if let Some(old_id) = game.disconnected_player_named(&username)
+            && let Some(left) = game.remove_player(old_id)
+        {
+            game.announce_left(left);
+        }
+
+        if game.usernames.contains(&username) {
+            self.send_error("username-taken");
+            return;
+        }
+
+        if game.player_count() >= game.settings.max_players {
+            self.send_error("game-full");
+            return;
+        }
+
+        if fresh_start {
+            game.saved_players.remove(&username);
+        }
+
+        let Some(position) = game.spawn_location_for(&username) else {
+            self.send_error("no-spawn-location");
+            return;
+        };
+
+        self.username = Some(username.clone());
+        self.game = Some(game_arc.clone());
+        self.game_input = state.game_loops.get(&game_id).map(GameLoop::input);
+
+        game.usernames.insert(username.clone());
+
+        state.clients.insert(self.id, self.clone());
+
+        game.connect(self.id, self.outbox.clone());
+        game.spawn_player(self.id, username.clone(), skin, position);
+
+        let resume_token = game.issue_resume_token(self.id);
+
+        self.send(Packet::PlayerRegistered {
+            id: self.id,
+            resume_token,
+            settings: game.settings.clone(),
+        });
+
+        game.send_recipe_list(self.id, &state.recipes);
+
+        game.broadcast(Packet::SystemMessage {
+            message: format!("{} joined the game", username),
+        });
+
+        self.log("Registered");
+    }
+
+    /// Moves this connection onto a player of an earlier one and sends it everything the player
+    /// was seeing.
+    async fn handle_resume(&mut self, resume_token: &str, state: &mut ServerState) {
+        if self.username.is_some() {
+            self.elog("Tried to resume while registered");
+            return;
+        }
+
+        let mut resumed = None;
+
+        for (game_id, game_arc) in &state.games {
+            if let Some((id, username)) = game_arc.lock().await.resume_player(resume_token) {
+                resumed = Some((*game_id, game_arc.clone(), id, username));
+                break;
             }
         }
+
+        let Some((game_id, game_arc, id, username)) = resumed else {
+            self.send_error("resume-failed");
+            return;
+        };
+
+        state.clients.remove(&self.id);
+
+        self.id = id;
+        self.username = Some(username);
+        self.game = Some(game_arc.clone());
+        self.game_input = state.game_loops.get(&game_id).map(GameLoop::input);
+
+        if let Some(old_client) = state.clients.insert(self.id, self.clone()) {
+            old_client.outbox.close();
+        }
+
+        let mut game = game_arc.lock().await;
+        game.connect(self.id, self.outbox.clone());
+
+        // The new connection has nothing loaded yet
+        let change = game.interest.full_view(self.id);
+        game.send_view_change(&self.id, change);
+
+        if let Some(dead_player) = game.dead_players.get(&self.id) {
+            self.send(Packet::PlayerDeath {
+                killer: None,
+                respawn_cooldown: (RESPAWN_COOLDOWN - dead_player.died_at.elapsed().as_secs_f32())
+                    .max(0.0),
+                can_spectate: ALLOW_SPECTATING,
+            });
+        }
+
+        let resume_token = game.issue_resume_token(self.id);
+
+        self.send(Packet::PlayerRegistered {
+            id: self.id,
+            resume_token,
+            settings: game.settings.clone(),
+        });
+
+        game.send_recipe_list(self.id, &state.recipes);
+
+        self.log("Resumed");
     }
 }
 
 impl ServerState {
-    pub fn create_game(&mut self, name: &str, seed: u32) -> Uuid {
-        let id = Uuid::new_v4();
-        let game = Game::new(id, name.to_string(), seed);
+    /// Adds a newly created game. The caller checks that its name is free.
+    pub fn add_game(&mut self, game: Game) -> Uuid {
+        let id = game.id;
 
         game.log("Created");
 
+        self.game_ids_by_name.insert(game.name.clone(), id);
+        self.games.insert(id, Arc::new(Mutex::new(game)));
+
+        id
+    }
+
+    /// Adds a game read back from its save in place of its unloaded entry.
+    pub fn restore_game(&mut self, game: Game) -> Uuid {
+        let id = game.id;
+
+        game.log("Restored");
+
+        self.unloaded_games.remove(&game.name);
+        self.game_ids_by_name.insert(game.name.clone(), id);
         self.games.insert(id, Arc::new(Mutex::new(game)));
-        self.game_ids_by_name.insert(name.to_string(), id);
 
         id
     }
+
+    /// Forgets a closed connection. Its player stays in the game for the grace period, if
+    /// there is one.
+    pub async fn disconnect_client(&mut self, id: Uuid, outbox: &Outbox) {
+        // The player may have been resumed by a newer connection already
+        if !self
+            .clients
+            .get(&id)
+            .is_some_and(|client| client.outbox.is_same_connection(outbox))
+        {
+            return;
+        }
+
+        let Some(client) = self.clients.remove(&id) else {
+            return;
+        };
+
+        client.log("Disconnected");
+        client.outbox.close();
+
+        let (Some(game_arc), Some(_)) = (&client.game, &client.username) else {
+            return;
+        };
+
+        let mut game = game_arc.lock().await;
+
+        if DISCONNECT_GRACE_PERIOD > 0.0 {
+            game.disconnect_player(id);
+            return;
+        }
+
+        if let Some(left) = game.remove_player(id) {
+            game.announce_left(left);
+        }
+    }
+
+    /// The id of the game with the name, loaded or not.
+    pub fn game_id_named(&self, name: &str) -> Option<Uuid> {
+        self.game_ids_by_name
+            .get(name)
+            .copied()
+            .or_else(|| self.unloaded_games.get(name).map(|game| game.id))
+    }
+
+    pub fn start_game_loop(&mut self, id: Uuid, state: SharedState) {
+        if let Some(game_arc) = self.games.get(&id) {
+            let idle_timeout = (self.config.game_idle_timeout_seconds > 0)
+                .then(|| Duration::from_secs(self.config.game_idle_timeout_seconds));
+
+            let game_loop = GameLoop::spawn(
+                game_arc.clone(),
+                TICK_RATE,
+                idle_timeout,
+                self.recipes.clone(),
+                state,
+            );
+            self.game_loops.insert(id, game_loop);
+        }
+    }
+
+    /// Removes a game from the server. Its loop has to be shut down by the caller once the
+    /// state is unlocked.
+    pub fn remove_game(&mut self, id: Uuid) -> Option<GameLoop> {
+        if let Some(game_arc) = self.games.remove(&id) {
+            self.game_ids_by_name.retain(|_, game_id| *game_id != id);
+
+            for client in self.clients.values_mut() {
+                if client
+                    .game
+                    .as_ref()
+                    .is_some_and(|game| Arc::ptr_eq(game, &game_arc))
+                {
+                    client.game = None;
+                    client.game_input = None;
+                    client.username = None;
+                }
+            }
+        }
+
+        self.game_loops.remove(&id)
+    }
+
+    /// Takes a game that was saved off the server, keeping it around as an unloaded game. Its
+    /// loop has to be shut down by the caller once the state is unlocked.
+    pub fn unload_game(&mut self, game: &mut Game) -> Option<GameLoop> {
+        game.removed = true;
+        game.log("Unloaded, nobody played for too long");
+
+        self.unloaded_games.insert(
+            game.name.clone(),
+            UnloadedGame {
+                id: game.id,
+                name: game.name.clone(),
+                seed: game.seed,
+                settings: game.settings.clone(),
+                owner: game.owner.clone(),
+                created_at: game.created_at,
+            },
+        );
+
+        self.remove_game(game.id)
+    }
+
+    /// Deletes a game, loaded or not, and sends its players back to the menu. Its loop has to be shut down
+    /// and its save deleted with `save::delete_game_save` by the caller once the state is
+    /// unlocked.
+    pub async fn delete_game(&mut self, id: Uuid) -> Option<GameLoop> {
+        if let Some(game_arc) = self.games.get(&id) {
+            let mut game = game_arc.lock().await;
+            game.removed = true;
+
+            game.log("Deleted");
+
+            for outbox in game.outboxes.values() {
+                outbox.send_error("game-deleted");
+            }
+        }
+
+        self.unloaded_games.retain(|_, game| game.id != id);
+
+        self.remove_game(id)
+    }
 }
 
 fn calculate_damage(target: &Entity, attacking_item: Option<Item>) -> i32 {
@@ -844,13 +2567,154 @@ fn calculate_damage(target: &Entity, attacking_item: Option<Item>) -> i32 {
             }
         }
 
-        damage *= match item.tool_material() {
-            Some(ToolMaterial::Iron) => 2,
-            _ => 1,
+        let multiplier = match (item.tool_material(), &target.value) {
+            (Some(material), EntityType::Player(_)) => material.damage_multiplier(),
+            (Some(material), target) if target.is_mob() => material.damage_multiplier(),
+            (Some(material), _) => material.gathering_speed(),
+            (None, _) => 1.0,
         };
 
-        damage
+        (damage as f32 * multiplier).round() as i32
     } else {
         5
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::{Discriminant, discriminant};
+    use std::sync::OnceLock;
+
+    /// One of every kind of entity the generator places in a few worlds.
+    fn generated_entity_types() -> &'static [EntityType] {
+        static TYPES: OnceLock<Vec<EntityType>> = OnceLock::new();
+        TYPES.get_or_init(generate_entity_types)
+    }
+
+    fn generate_entity_types() -> Vec<EntityType> {
+        let settings = GameSettings::default();
+        let mut types: HashMap<Discriminant<EntityType>, EntityType> = HashMap::new();
+
+        for seed in 0..2 {
+            let generator = TerrainGenerator::new(seed, &settings);
+
+            for x in 0..settings.world_size {
+                for y in 0..settings.world_size {
+                    if let Some(entity) = generator.get_entity(x, y) {
+                        types
+                            .entry(discriminant(&entity.value))
+                            .or_insert(entity.value);
+                    }
+                }
+            }
+        }
+
+        types.into_values().collect()
+    }
+
+    /// Items a player can get by breaking generated entities.
+    fn gatherable_items() -> HashSet<Item> {
+        generated_entity_types()
+            .iter()
+            // Ores only drop some of the time
+            .flat_map(|entity_type| (0..1000).flat_map(|_| entity_type.loot()))
+            .map(|stack| stack.item)
+            .collect()
+    }
+
+    #[test]
+    fn the_smallest_worlds_have_a_spawn_location() {
+        for (world_size, water_edge_size) in [(64, 8), (128, 24)] {
+            let settings = GameSettings {
+                world_size,
+                water_edge_size,
+                ..GameSettings::default()
+            };
+            assert!(settings.validate().is_ok());
+
+            for seed in 0..16 {
+                let game = Game::new(Uuid::new_v4(), "test".to_string(), seed, settings.clone());
+                assert!(
+                    game.get_new_spawn_location().is_some(),
+                    "no spawn location in {}/{} with seed {}",
+                    world_size,
+                    water_edge_size,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_world_entity_with_loot_is_generated() {
+        let generated: HashSet<_> = generated_entity_types().iter().map(discriminant).collect();
+
+        for entity_type in [
+            EntityType::ForestTree,
+            EntityType::SpruceTree,
+            EntityType::JungleTree,
+            EntityType::Cactus,
+            EntityType::TreeStump,
+            EntityType::IceSpike,
+            EntityType::Bush,
+            EntityType::Stone,
+            EntityType::BigStone,
+            EntityType::TreeLog,
+            EntityType::TallGrass,
+            EntityType::SeaShell,
+        ] {
+            assert!(
+                !entity_type.loot().is_empty(),
+                "{:?} drops nothing",
+                entity_type
+            );
+            assert!(
+                generated.contains(&discriminant(&entity_type)),
+                "{:?} drops loot but is never generated",
+                entity_type
+            );
+        }
+    }
+
+    #[test]
+    fn berries_can_be_gathered() {
+        let gatherable = gatherable_items();
+
+        assert!(gatherable.contains(&Item::Berries));
+        assert!(gatherable.iter().any(|item| item.food_value().is_some()));
+    }
+
+    #[test]
+    fn every_tool_tier_can_be_crafted_from_gathered_resources() {
+        let gatherable = gatherable_items();
+        let recipes = RecipeRegistry::default();
+
+        for recipe in recipes.recipes() {
+            let mut inventory = Inventory {
+                slots: vec![None; 6],
+                selected: 0,
+            };
+
+            for input in &recipe.inputs {
+                assert!(
+                    gatherable.contains(&input.item),
+                    "{} needs {:?}, which cannot be gathered",
+                    recipe.name,
+                    input.item
+                );
+                assert_eq!(inventory.add(*input), 0);
+            }
+
+            assert!(
+                recipes.craft(&recipe.name, &mut inventory).is_ok(),
+                "{} could not be crafted",
+                recipe.name
+            );
+
+            for output in &recipe.outputs {
+                assert_eq!(inventory.count(output.item), output.count);
+            }
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use actix_web::web::Bytes;
+use actix_ws::Session;
+use serde::Serialize;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::config::{OUTBOX_CAPACITY, OUTBOX_FULL_LENGTH, OUTBOX_FULL_TIMEOUT_SECONDS};
+use crate::packet::{EncodedPacket, Encoding, ErrorPacket, Packet};
+
+pub enum Outgoing {
+    Packet(Packet),
+    Error(ErrorPacket),
+    Pong(Bytes),
+    Close,
+}
+
+/// Counters shared by the outboxes of all connections.
+#[derive(Default)]
+pub struct OutboxMetrics {
+    coalesced_moves: AtomicU64,
+    evicted_clients: AtomicU64,
+}
+
+/// How far behind the connections are, as reported by `/metrics`.
+#[derive(Debug, Default, Serialize)]
+pub struct OutboxStats {
+    pub clients: usize,
+    pub queued: usize,
+    pub deepest_queue: usize,
+    pub peak_queue: usize,
+    pub coalesced_moves: u64,
+    pub evicted_clients: u64,
+}
+
+impl OutboxMetrics {
+    /// Sums up the queues of the given connections along with the counters since startup.
+    pub fn stats<'a>(&self, outboxes: impl IntoIterator<Item = &'a Outbox>) -> OutboxStats {
+        let mut stats = OutboxStats {
+            coalesced_moves: self.coalesced_moves.load(Ordering::Relaxed),
+            evicted_clients: self.evicted_clients.load(Ordering::Relaxed),
+            ..OutboxStats::default()
+        };
+
+        for outbox in outboxes {
+            let queue = outbox.shared.queue.lock().unwrap();
+
+            stats.clients += 1;
+            stats.queued += queue.messages.len();
+            stats.deepest_queue = stats.deepest_queue.max(queue.messages.len());
+            stats.peak_queue = stats.peak_queue.max(queue.peak_length);
+        }
+
+        stats
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    messages: VecDeque<Outgoing>,
+    /// Entities with a move among `messages`, which a newer move of them replaces
+    moving: HashSet<Uuid>,
+    full_since: Option<Instant>,
+    peak_length: usize,
+    closed: bool,
+    evicted: bool,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Notify,
+    evicted: Notify,
+    metrics: Arc<OutboxMetrics>,
+}
+
+/// The sending half of a connection. Messages are queued and written to the socket by the
+/// connection's own task, so sending never waits on a socket.
+///
+/// Moves of an entity replace its moves that are still queued. A queue that has
+/// `OUTBOX_FULL_LENGTH` messages waiting counts as full, and the connection is dropped once it
+/// stays full for `OUTBOX_FULL_TIMEOUT_SECONDS` or reaches `OUTBOX_CAPACITY` messages. Its
+/// player can then resume and gets everything it sees again.
+#[derive(Clone)]
+pub struct Outbox {
+    shared: Arc<Shared>,
+}
+
+/// The receiving half of a connection, closing the outbox when dropped.
+pub struct OutboxReceiver {
+    shared: Arc<Shared>,
+}
+
+/// The ids of the entities a packet moves.
+fn moved_entities(packet: &Packet) -> Vec<Uuid> {
+    match packet {
+        Packet::EntityMove { id, .. } => vec![*id],
+        Packet::EntityMoveBatch { moves } => moves.iter().map(|movement| movement.id).collect(),
+        _ => vec![],
+    }
+}
+
+impl Queue {
+    /// Removes the queued moves of `entities`. Returns how many there were.
+    fn remove_moves(&mut self, entities: &HashSet<Uuid>) -> u64 {
+        let mut removed = 0;
+
+        self.messages.retain_mut(|message| match message {
+            Outgoing::Packet(Packet::EntityMove { id, .. }) if entities.contains(id) => {
+                removed += 1;
+                false
+            }
+            Outgoing::Packet(Packet::EntityMoveBatch { moves }) => {
+                let before = moves.len();
+                moves.retain(|movement| !entities.contains(&movement.id));
+                removed += before - moves.len();
+                !moves.is_empty()
+            }
+            _ => true,
+        });
+
+        removed as u64
+    }
+
+    fn is_full(&self) -> bool {
+        self.messages.len() >= OUTBOX_FULL_LENGTH
+    }
+
+    fn close(&mut self) {
+        self.messages.clear();
+        self.moving.clear();
+        self.closed = true;
+    }
+}
+
+impl Outbox {
+    pub fn channel(metrics: Arc<OutboxMetrics>) -> (Self, OutboxReceiver) {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue::default()),
+            ready: Notify::new(),
+            evicted: Notify::new(),
+            metrics,
+        });
+
+        (
+            Self {
+                shared: shared.clone(),
+            },
+            OutboxReceiver { shared },
+        )
+    }
+
+    fn push(&self, outgoing: Outgoing) {
+        let mut queue = self.shared.queue.lock().unwrap();
+
+        if queue.closed {
+            return;
+        }
+
+        if let Outgoing::Packet(packet) = &outgoing {
+            let moved = moved_entities(packet);
+            let stale: HashSet<_> = moved
+                .iter()
+                .filter(|id| queue.moving.contains(id))
+                .copied()
+                .collect();
+
+            if !stale.is_empty() {
+                let removed = queue.remove_moves(&stale);
+                self.shared
+                    .metrics
+                    .coalesced_moves
+                    .fetch_add(removed, Ordering::Relaxed);
+            }
+
+            queue.moving.extend(moved);
+        }
+
+        queue.messages.push_back(outgoing);
+        queue.peak_length = queue.peak_length.max(queue.messages.len());
+
+        if queue.is_full() {
+            let full_since = *queue.full_since.get_or_insert_with(Instant::now);
+            let timeout = Duration::from_secs_f32(OUTBOX_FULL_TIMEOUT_SECONDS);
+
+            if full_since.elapsed() >= timeout || queue.messages.len() >= OUTBOX_CAPACITY {
+                self.evict_queue(&mut queue);
+                return;
+            }
+        }
+
+        self.shared.ready.notify_one();
+    }
+
+    fn evict_queue(&self, queue: &mut Queue) {
+        queue.close();
+        queue.evicted = true;
+        self.shared
+            .metrics
+            .evicted_clients
+            .fetch_add(1, Ordering::Relaxed);
+        self.shared.evicted.notify_one();
+        // Wakes the writer if it is waiting for messages rather than on the socket
+        self.shared.ready.notify_one();
+    }
+
+    /// Queues a packet, it is dropped if the connection is gone.
+    pub fn send(&self, packet: Packet) {
+        self.push(Outgoing::Packet(packet));
+    }
+
+    pub fn send_error<S: AsRef<str>>(&self, error: S) {
+        self.push(Outgoing::Error(ErrorPacket::new(error)));
+    }
+
+    pub fn pong(&self, msg: Bytes) {
+        self.push(Outgoing::Pong(msg));
+    }
+
+    /// Closes the socket once everything queued before has been written.
+    pub fn close(&self) {
+        self.push(Outgoing::Close);
+    }
+
+    pub fn is_same_connection(&self, other: &Outbox) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    /// Drops the connection right away, throwing away what is still queued.
+    pub fn evict(&self) {
+        let mut queue = self.shared.queue.lock().unwrap();
+
+        if !queue.evicted {
+            self.evict_queue(&mut queue);
+        }
+    }
+
+    /// Whether the connection was dropped for falling behind.
+    pub fn is_evicted(&self) -> bool {
+        self.shared.queue.lock().unwrap().evicted
+    }
+}
+
+impl OutboxReceiver {
+    /// Waits for the next message. Returns `None` once the connection was dropped for falling
+    /// behind, which throws away what was still queued.
+    pub async fn recv(&mut self) -> Option<Outgoing> {
+        loop {
+            {
+                let mut queue = self.shared.queue.lock().unwrap();
+
+                if let Some(outgoing) = queue.messages.pop_front() {
+                    if let Outgoing::Packet(packet) = &outgoing {
+                        for id in moved_entities(packet) {
+                            queue.moving.remove(&id);
+                        }
+                    }
+
+                    if !queue.is_full() {
+                        queue.full_since = None;
+                    }
+
+                    return Some(outgoing);
+                }
+
+                if queue.closed {
+                    return None;
+                }
+            }
+
+            self.shared.ready.notified().await;
+        }
+    }
+
+    /// Resolves once the connection is dropped for falling behind.
+    pub async fn evicted(&self) {
+        self.shared.evicted.notified().await;
+    }
+}
+
+impl Drop for OutboxReceiver {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().close();
+    }
+}
+
+async fn write(
+    session: &mut Session,
+    outgoing: Outgoing,
+    encoding: Encoding,
+) -> Result<(), actix_ws::Closed> {
+    let encoded = match outgoing {
+        Outgoing::Packet(packet) => encoding.encode(&packet),
+        Outgoing::Error(error) => encoding.encode(&error),
+        Outgoing::Pong(msg) => return session.pong(&msg).await,
+        Outgoing::Close => return Ok(()),
+    };
+
+    match encoded {
+        EncodedPacket::Text(text) => session.text(text).await,
+        EncodedPacket::Binary(bytes) => session.binary(bytes).await,
+    }
+}
+
+/// Writes the queued messages of a connection to its socket until it is closed. A connection
+/// that is dropped for not keeping up is cut off without waiting for the socket, dropping the
+/// session ends the response, which closes the socket.
+pub async fn write_outgoing(mut session: Session, mut rx: OutboxReceiver, encoding: Encoding) {
+    loop {
+        let Some(outgoing) = rx.recv().await else {
+            return;
+        };
+
+        if let Outgoing::Close = outgoing {
+            break;
+        }
+
+        let result = tokio::select! {
+            result = write(&mut session, outgoing, encoding) => result,
+            _ = rx.evicted() => return,
+        };
+
+        if result.is_err() {
+            return;
+        }
+    }
+
+    session.close(None).await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Vec2;
+    use crate::packet::EntityMovement;
+
+    fn entity_move(id: Uuid, x: f32) -> Packet {
+        Packet::EntityMove {
+            id,
+            new_position: Vec2 { x, y: 0.0 },
+        }
+    }
+
+    fn channel() -> (Outbox, OutboxReceiver, Arc<OutboxMetrics>) {
+        let metrics = Arc::new(OutboxMetrics::default());
+        let (outbox, rx) = Outbox::channel(metrics.clone());
+        (outbox, rx, metrics)
+    }
+
+    /// Closes the outbox and reads everything queued before the close.
+    async fn drain(outbox: &Outbox, rx: &mut OutboxReceiver) -> Vec<Packet> {
+        outbox.close();
+
+        let mut packets = vec![];
+        loop {
+            match rx.recv().await {
+                Some(Outgoing::Packet(packet)) => packets.push(packet),
+                Some(Outgoing::Close) => return packets,
+                Some(_) => {}
+                None => panic!("the outbox was evicted"),
+            }
+        }
+    }
+
+    /// Queues messages that are never coalesced until the outbox holds `length` of them.
+    fn fill(outbox: &Outbox, length: usize) {
+        for _ in 0..length {
+            outbox.pong(Bytes::new());
+        }
+    }
+
+    fn queue_length(outbox: &Outbox) -> usize {
+        outbox.shared.queue.lock().unwrap().messages.len()
+    }
+
+    #[actix_web::test]
+    async fn a_move_replaces_the_queued_moves_of_its_entity() {
+        let (outbox, mut rx, metrics) = channel();
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        outbox.send(entity_move(a, 1.0));
+        outbox.send(entity_move(b, 1.0));
+        outbox.send(entity_move(a, 2.0));
+        outbox.send(Packet::EntityMoveBatch {
+            moves: vec![
+                EntityMovement {
+                    id: a,
+                    new_position: Vec2 { x: 3.0, y: 0.0 },
+                },
+                EntityMovement {
+                    id: c,
+                    new_position: Vec2 { x: 1.0, y: 0.0 },
+                },
+            ],
+        });
+        outbox.send(entity_move(c, 2.0));
+
+        let packets = drain(&outbox, &mut rx).await;
+
+        // The batch keeps the move of `a` but loses the one of `c`
+        assert_eq!(packets.len(), 3);
+        assert!(matches!(packets[0], Packet::EntityMove { id, .. } if id == b));
+        assert!(matches!(
+            &packets[1],
+            Packet::EntityMoveBatch { moves } if moves.len() == 1 && moves[0].id == a
+        ));
+        assert!(matches!(
+            packets[2],
+            Packet::EntityMove { id, new_position } if id == c && new_position.x == 2.0
+        ));
+        assert_eq!(metrics.stats([&outbox]).coalesced_moves, 3);
+    }
+
+    #[actix_web::test]
+    async fn moves_already_sent_are_not_coalesced() {
+        let (outbox, mut rx, metrics) = channel();
+        let id = Uuid::new_v4();
+
+        outbox.send(entity_move(id, 1.0));
+        assert!(matches!(
+            rx.recv().await,
+            Some(Outgoing::Packet(Packet::EntityMove { .. }))
+        ));
+
+        outbox.send(entity_move(id, 2.0));
+
+        assert_eq!(drain(&outbox, &mut rx).await.len(), 1);
+        assert_eq!(metrics.stats([&outbox]).coalesced_moves, 0);
+    }
+
+    #[actix_web::test]
+    async fn a_full_queue_is_evicted_once_it_stays_full_for_the_timeout() {
+        let (outbox, mut rx, metrics) = channel();
+
+        fill(&outbox, OUTBOX_FULL_LENGTH + 1);
+        assert!(!outbox.is_evicted());
+
+        let timeout = Duration::from_secs_f32(OUTBOX_FULL_TIMEOUT_SECONDS);
+        outbox.shared.queue.lock().unwrap().full_since = Some(Instant::now() - timeout);
+
+        outbox.pong(Bytes::new());
+
+        assert!(outbox.is_evicted());
+        assert_eq!(queue_length(&outbox), 0);
+        assert!(rx.recv().await.is_none());
+
+        // Nothing is queued for an evicted connection anymore
+        outbox.send(entity_move(Uuid::new_v4(), 1.0));
+        assert!(rx.recv().await.is_none());
+
+        assert_eq!(metrics.stats([&outbox]).evicted_clients, 1);
+    }
+
+    #[actix_web::test]
+    async fn a_queue_that_drains_below_full_is_not_evicted() {
+        let (outbox, mut rx, metrics) = channel();
+
+        fill(&outbox, OUTBOX_FULL_LENGTH);
+        assert!(outbox.shared.queue.lock().unwrap().full_since.is_some());
+
+        rx.recv().await;
+        assert!(outbox.shared.queue.lock().unwrap().full_since.is_none());
+
+        fill(&outbox, 1);
+
+        assert!(!outbox.is_evicted());
+        assert_eq!(drain(&outbox, &mut rx).await.len(), 0);
+        assert_eq!(metrics.stats([&outbox]).evicted_clients, 0);
+    }
+
+    #[actix_web::test]
+    async fn a_queue_reaching_capacity_is_evicted_right_away() {
+        let (outbox, mut rx, metrics) = channel();
+
+        fill(&outbox, OUTBOX_CAPACITY - 1);
+        assert!(!outbox.is_evicted());
+
+        fill(&outbox, 1);
+
+        assert!(outbox.is_evicted());
+        assert!(rx.recv().await.is_none());
+        assert_eq!(metrics.stats([&outbox]).evicted_clients, 1);
+    }
+
+    #[actix_web::test]
+    async fn evicting_wakes_a_waiting_receiver() {
+        let (outbox, mut rx, metrics) = channel();
+
+        let evicting = outbox.clone();
+        actix_web::rt::spawn(async move { evicting.evict() });
+
+        assert!(rx.recv().await.is_none());
+
+        // Evicting again is not counted twice
+        outbox.evict();
+        assert_eq!(metrics.stats([&outbox]).evicted_clients, 1);
+    }
+
+    #[actix_web::test]
+    async fn stats_sum_up_the_queues() {
+        let metrics = Arc::new(OutboxMetrics::default());
+        let (first, _first_rx) = Outbox::channel(metrics.clone());
+        let (second, mut second_rx) = Outbox::channel(metrics.clone());
+
+        fill(&first, 3);
+        fill(&second, 5);
+        for _ in 0..4 {
+            second_rx.recv().await;
+        }
+
+        let stats = metrics.stats([&first, &second]);
+
+        assert_eq!(stats.clients, 2);
+        assert_eq!(stats.queued, 4);
+        assert_eq!(stats.deepest_queue, 3);
+        assert_eq!(stats.peak_queue, 5);
+    }
+}
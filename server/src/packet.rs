@@ -1,9 +1,40 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::model::{Entity, Item, Vec2};
+use crate::crafting::AvailableRecipe;
+use crate::inventory::ItemStack;
+use crate::model::{Entity, Vec2};
+use crate::settings::GameSettings;
 use crate::terrain::TerrainChunk;
 
+/// How packets are encoded on a connection, chosen with `/ws?encoding=`. Clients may send
+/// packets in either encoding, the server answers in the chosen one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Encoding {
+    /// Text frames, easy to read while debugging
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+
+    /// Binary frames with MessagePack maps
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+impl Encoding {
+    pub fn encode<T: Serialize>(self, value: &T) -> EncodedPacket {
+        match self {
+            Encoding::Json => EncodedPacket::Text(serde_json::to_string(value).unwrap()),
+            Encoding::MessagePack => EncodedPacket::Binary(rmp_serde::to_vec_named(value).unwrap()),
+        }
+    }
+}
+
+pub enum EncodedPacket {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorPacket {
     pub error: String,
@@ -17,21 +48,39 @@ impl ErrorPacket {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMovement {
+    pub id: Uuid,
+    pub new_position: Vec2,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "packet_type")]
 pub enum Packet {
     #[serde(rename = "player_register")]
     PlayerRegister {
         game_name: String,
-        username: String,
+        token: String,
         skin: i32,
+        #[serde(default)]
+        fresh_start: bool,
     },
 
     #[serde(rename = "player_registered")]
-    PlayerRegistered { id: Uuid },
+    PlayerRegistered {
+        id: Uuid,
+        resume_token: String,
+        settings: GameSettings,
+    },
+
+    #[serde(rename = "player_resume")]
+    PlayerResume { resume_token: String },
+
+    #[serde(rename = "terrain_chunk_batch")]
+    TerrainChunkBatch { chunks: Vec<TerrainChunk> },
 
-    #[serde(rename = "terrain_chunk")]
-    TerrainChunk { chunk: TerrainChunk },
+    #[serde(rename = "terrain_chunk_unload")]
+    TerrainChunkUnload { chunks: Vec<Vec2> },
 
     #[serde(rename = "entity_load")]
     EntityLoad { entity: Entity },
@@ -42,6 +91,9 @@ pub enum Packet {
     #[serde(rename = "entity_move")]
     EntityMove { id: Uuid, new_position: Vec2 },
 
+    #[serde(rename = "entity_move_batch")]
+    EntityMoveBatch { moves: Vec<EntityMovement> },
+
     #[serde(rename = "chat_message_send")]
     ChatMessageSend { message: String },
 
@@ -62,17 +114,51 @@ pub enum Packet {
     EntityDamage { id: Uuid, new_health: i32 },
 
     #[serde(rename = "entity_death")]
-    EntityDeath {
-        id: Uuid,
-        // TODO: item drops
-    },
+    EntityDeath { id: Uuid },
 
     #[serde(rename = "inventory_state")]
     InventoryState {
-        slots: Vec<Option<Item>>,
+        slots: Vec<Option<ItemStack>>,
         selected: i32,
     },
 
     #[serde(rename = "inventory_select")]
     InventorySelect { selected: i32 },
+
+    #[serde(rename = "item_pickup")]
+    ItemPickup { id: Uuid },
+
+    #[serde(rename = "inventory_move")]
+    InventoryMove { from: i32, to: i32 },
+
+    #[serde(rename = "inventory_split")]
+    InventorySplit { from: i32, to: i32, count: u32 },
+
+    #[serde(rename = "craft")]
+    Craft { recipe: String },
+
+    #[serde(rename = "recipe_list_request")]
+    RecipeListRequest,
+
+    #[serde(rename = "recipe_list")]
+    RecipeList { recipes: Vec<AvailableRecipe> },
+
+    #[serde(rename = "player_death")]
+    PlayerDeath {
+        killer: Option<String>,
+        respawn_cooldown: f32,
+        can_spectate: bool,
+    },
+
+    #[serde(rename = "player_respawn")]
+    PlayerRespawn,
+
+    #[serde(rename = "player_respawned")]
+    PlayerRespawned,
+
+    #[serde(rename = "use_item")]
+    UseItem { slot: i32 },
+
+    #[serde(rename = "player_stats")]
+    PlayerStats { health: i32, hunger: i32 },
 }
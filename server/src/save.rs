@@ -0,0 +1,290 @@
+use actix_web::{rt, web};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use uuid::Uuid;
+
+use crate::config::*;
+use crate::game_loop::GameLoop;
+use crate::inventory::Inventory;
+use crate::model::{Entity, Game, LifetimeStats, SharedState, UnloadedGame, Vec2};
+use crate::settings::GameSettings;
+
+/// A game on disk. Only the differences to the world generated from `seed` are stored.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GameSave {
+    pub id: Uuid,
+    pub name: String,
+    pub seed: u32,
+    #[serde(default)]
+    pub settings: GameSettings,
+    pub owner: Option<String>,
+    pub created_at: SystemTime,
+    pub harvested: Vec<HarvestedEntity>,
+    pub damaged: Vec<DamagedEntity>,
+    pub entities: Vec<Entity>,
+    pub players: HashMap<String, SavedPlayer>,
+    pub stats: HashMap<String, LifetimeStats>,
+}
+
+/// A player that was alive when the game was saved. They continue from here when they join
+/// again with the same username.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedPlayer {
+    pub position: Vec2,
+    pub health: i32,
+    pub hunger: i32,
+    pub inventory: Inventory,
+}
+
+/// A generated world entity that was destroyed and has not regrown yet.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HarvestedEntity {
+    pub position: Vec2,
+    pub regrows_in: f32,
+}
+
+/// A generated world entity that was damaged but not destroyed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DamagedEntity {
+    pub position: Vec2,
+    pub health: i32,
+}
+
+fn save_path(dir: &Path, id: Uuid) -> PathBuf {
+    dir.join(format!("{id}.json"))
+}
+
+/// Writes the save next to the old one first, so a crash while saving never leaves a
+/// truncated file behind.
+pub fn write_save(dir: &Path, save: &GameSave) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let path = save_path(dir, save.id);
+    let tmp_path = path.with_extension("json.tmp");
+
+    fs::write(&tmp_path, serde_json::to_vec(save)?)?;
+    fs::rename(tmp_path, path)
+}
+
+pub fn delete_save(dir: &Path, id: Uuid) -> io::Result<()> {
+    match fs::remove_file(save_path(dir, id)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+pub fn read_save(dir: &Path, id: Uuid) -> io::Result<GameSave> {
+    let data = fs::read(save_path(dir, id))?;
+
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Reads what the games list needs from every save in `dir`, without restoring the worlds.
+/// The games are loaded when someone opens them.
+pub fn load_unloaded_games(dir: &Path) -> Vec<UnloadedGame> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return vec![],
+        Err(e) => {
+            error!("[SAVE] Could not read {}: {}", dir.display(), e);
+            return vec![];
+        }
+    };
+
+    let mut games = vec![];
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+
+        match fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice(&data).map_err(|e| e.to_string()))
+        {
+            Ok(game) => games.push(game),
+            Err(e) => error!("[SAVE] Could not load {}: {}", path.display(), e),
+        }
+    }
+
+    games
+}
+
+/// Runs a blocking save operation off the async executor.
+async fn run_blocking<T, F>(f: F) -> io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    web::block(f).await.map_err(io::Error::other)?
+}
+
+/// Saves every game. The server state is only locked while collecting the games and each game
+/// only while copying it, the files are written after that.
+pub async fn save_games(state: &SharedState) {
+    let (games, dir, save_lock) = {
+        let state = state.lock().await;
+        let games: Vec<_> = state.games.values().cloned().collect();
+        (
+            games,
+            state.config.save_dir.clone(),
+            state.save_lock.clone(),
+        )
+    };
+
+    for game_arc in games {
+        // Saves are deleted only while no save is written, and a deleted or unloaded game is
+        // skipped, so an outdated copy cannot be written over its save
+        let _saving = save_lock.lock().await;
+
+        let save = {
+            let game = game_arc.lock().await;
+
+            if game.is_removed() {
+                continue;
+            }
+
+            game.to_save()
+        };
+
+        let (id, name) = (save.id, save.name.clone());
+        let dir = dir.clone();
+
+        match run_blocking(move || write_save(&dir, &save)).await {
+            Ok(()) => info!("[SAVE] Saved game {} ({})", id, name),
+            Err(e) => error!("[SAVE] Could not save game {} ({}): {}", id, name, e),
+        }
+    }
+}
+
+/// Deletes the save of a game that was deleted with `ServerState::delete_game`.
+pub async fn delete_game_save(state: &SharedState, id: Uuid) {
+    let (dir, save_lock) = {
+        let state = state.lock().await;
+        (state.config.save_dir.clone(), state.save_lock.clone())
+    };
+
+    let _saving = save_lock.lock().await;
+
+    if let Err(e) = run_blocking(move || delete_save(&dir, id)).await {
+        error!("[SAVE] Could not delete save of game {}: {}", id, e);
+    }
+}
+
+/// Saves a game nobody played for too long and takes it off the server, its save stays around
+/// to load it again. Returns the game's loop, which the caller has to stop. The game stays
+/// loaded if it could not be saved or someone joined in the meantime.
+pub async fn unload_game(state: &SharedState, id: Uuid) -> Option<GameLoop> {
+    let (game_arc, dir, save_lock) = {
+        let state = state.lock().await;
+        let game_arc = state.games.get(&id)?.clone();
+        (
+            game_arc,
+            state.config.save_dir.clone(),
+            state.save_lock.clone(),
+        )
+    };
+
+    let _saving = save_lock.lock().await;
+
+    let save = {
+        let game = game_arc.lock().await;
+
+        if game.is_removed() || game.player_count() > 0 {
+            return None;
+        }
+
+        game.to_save()
+    };
+
+    if let Err(e) = run_blocking(move || write_save(&dir, &save)).await {
+        let mut game = game_arc.lock().await;
+        game.elog(format!("Could not save before unloading: {}", e));
+        game.reset_idle();
+        return None;
+    }
+
+    let mut state = state.lock().await;
+    let mut game = game_arc.lock().await;
+
+    // Players stay in the game for a while after leaving, so anyone who joined while the
+    // save was written is still counted here
+    if game.is_removed() || game.player_count() > 0 {
+        return None;
+    }
+
+    state.unload_game(&mut game)
+}
+
+/// Makes sure the game with the name is loaded, reading it back from its save and starting its
+/// loop if it was unloaded. Returns whether there is such a game.
+pub async fn load_game(state: &SharedState, name: &str) -> bool {
+    let (id, dir, save_lock) = {
+        let state = state.lock().await;
+
+        if state.game_ids_by_name.contains_key(name) {
+            return true;
+        }
+
+        let Some(unloaded) = state.unloaded_games.get(name) else {
+            return false;
+        };
+
+        (
+            unloaded.id,
+            state.config.save_dir.clone(),
+            state.save_lock.clone(),
+        )
+    };
+
+    let read = {
+        let _saving = save_lock.lock().await;
+        // Generating the world again takes a while, so it is done before locking the state
+        run_blocking(move || read_save(&dir, id).map(Game::from_save)).await
+    };
+
+    let mut locked_state = state.lock().await;
+
+    // Another request may have loaded or deleted the game while its save was read
+    if locked_state
+        .unloaded_games
+        .get(name)
+        .is_none_or(|unloaded| unloaded.id != id)
+    {
+        return locked_state.game_ids_by_name.contains_key(name);
+    }
+
+    match read {
+        Ok(game) => {
+            let id = locked_state.restore_game(game);
+            locked_state.start_game_loop(id, state.clone());
+            true
+        }
+        Err(e) => {
+            error!("[SAVE] Could not load game {} ({}): {}", id, name, e);
+            false
+        }
+    }
+}
+
+pub fn spawn_autosave(state: SharedState) -> JoinHandle<()> {
+    rt::spawn(async move {
+        let period = Duration::from_secs(SAVE_INTERVAL_SECONDS);
+        let mut interval = rt::time::interval_at(rt::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            save_games(&state).await;
+        }
+    })
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::*;
+
+/// World and gameplay parameters chosen when a game is created.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameSettings {
+    pub world_size: i32,
+    pub water_edge_size: i32,
+    pub entity_spawn_rate: f64,
+    pub pvp: bool,
+    pub max_players: usize,
+    pub max_player_health: i32,
+    pub max_world_entity_health: i32,
+    pub view_range: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            world_size: DEFAULT_WORLD_SIZE,
+            water_edge_size: DEFAULT_WATER_EDGE_SIZE,
+            entity_spawn_rate: DEFAULT_WORLD_ENTITY_SPAWN_RATE,
+            pvp: DEFAULT_PVP,
+            max_players: DEFAULT_MAX_PLAYERS,
+            max_player_health: DEFAULT_MAX_PLAYER_HEALTH,
+            max_world_entity_health: DEFAULT_MAX_WORLD_ENTITY_HEALTH,
+            view_range: DEFAULT_VIEW_RANGE,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Returns the name of the first setting that is out of range.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(64..=1024).contains(&self.world_size) || self.world_size % CHUNK_SIZE != 0 {
+            return Err("world_size");
+        }
+
+        if self.water_edge_size < 0
+            || self.world_size - 2 * self.no_spawn_border() < MIN_SPAWN_AREA_SIZE
+        {
+            return Err("water_edge_size");
+        }
+
+        if !(0.0..=1.0).contains(&self.entity_spawn_rate) {
+            return Err("entity_spawn_rate");
+        }
+
+        if !(1..=256).contains(&self.max_players) {
+            return Err("max_players");
+        }
+
+        if !(1..=10_000).contains(&self.max_player_health) {
+            return Err("max_player_health");
+        }
+
+        if !(1..=10_000).contains(&self.max_world_entity_health) {
+            return Err("max_world_entity_health");
+        }
+
+        if !(8.0..=64.0).contains(&self.view_range) {
+            return Err("view_range");
+        }
+
+        Ok(())
+    }
+
+    /// Players spawn at least this far from the world border.
+    pub fn no_spawn_border(&self) -> i32 {
+        self.water_edge_size * 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_border(world_size: i32, water_edge_size: i32) -> GameSettings {
+        GameSettings {
+            world_size,
+            water_edge_size,
+            ..GameSettings::default()
+        }
+    }
+
+    #[test]
+    fn water_edge_leaves_room_to_spawn() {
+        assert!(GameSettings::default().validate().is_ok());
+        assert!(with_border(64, 8).validate().is_ok());
+        assert!(with_border(128, 24).validate().is_ok());
+
+        assert_eq!(with_border(64, 9).validate(), Err("water_edge_size"));
+        assert_eq!(with_border(64, 15).validate(), Err("water_edge_size"));
+        assert_eq!(with_border(128, 31).validate(), Err("water_edge_size"));
+        assert_eq!(with_border(64, -1).validate(), Err("water_edge_size"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet, hash_map};
+use uuid::Uuid;
+
+use crate::config::CHUNK_SIZE;
+use crate::model::{Entity, Vec2};
+
+pub type ChunkCoords = (i32, i32);
+
+pub fn get_chunk_coords_of(position: Vec2) -> ChunkCoords {
+    (
+        (position.x / CHUNK_SIZE as f32).floor() as i32,
+        (position.y / CHUNK_SIZE as f32).floor() as i32,
+    )
+}
+
+/// The entities of a game, bucketed by the chunk they are in so lookups by area do not have
+/// to go through the whole world.
+///
+/// Positions must only be changed with `set_position`, which moves the entity to its new
+/// bucket. Everything else about an entity can be changed through `get_mut`.
+#[derive(Default)]
+pub struct EntityMap {
+    entities: HashMap<Uuid, Entity>,
+    chunks: HashMap<ChunkCoords, HashSet<Uuid>>,
+}
+
+impl EntityMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<&Entity> {
+        self.entities.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &Uuid) -> Option<&mut Entity> {
+        self.entities.get_mut(id)
+    }
+
+    pub fn contains_key(&self, id: &Uuid) -> bool {
+        self.entities.contains_key(id)
+    }
+
+    pub fn values(&self) -> hash_map::Values<'_, Uuid, Entity> {
+        self.entities.values()
+    }
+
+    pub fn values_mut(&mut self) -> hash_map::ValuesMut<'_, Uuid, Entity> {
+        self.entities.values_mut()
+    }
+
+    /// Adds an entity, replacing the one with the same id.
+    pub fn insert(&mut self, entity: Entity) -> Option<Entity> {
+        let replaced = self.remove(&entity.id);
+
+        self.chunks
+            .entry(get_chunk_coords_of(entity.position))
+            .or_default()
+            .insert(entity.id);
+        self.entities.insert(entity.id, entity);
+
+        replaced
+    }
+
+    pub fn remove(&mut self, id: &Uuid) -> Option<Entity> {
+        let entity = self.entities.remove(id)?;
+        self.unlink(id, get_chunk_coords_of(entity.position));
+
+        Some(entity)
+    }
+
+    pub fn set_position(&mut self, id: &Uuid, position: Vec2) {
+        let Some(entity) = self.entities.get_mut(id) else {
+            return;
+        };
+
+        let prev_chunk = get_chunk_coords_of(entity.position);
+        let new_chunk = get_chunk_coords_of(position);
+        entity.position = position;
+
+        if prev_chunk != new_chunk {
+            self.unlink(id, prev_chunk);
+            self.chunks.entry(new_chunk).or_default().insert(*id);
+        }
+    }
+
+    fn unlink(&mut self, id: &Uuid, chunk: ChunkCoords) {
+        if let hash_map::Entry::Occupied(mut bucket) = self.chunks.entry(chunk) {
+            bucket.get_mut().remove(id);
+
+            if bucket.get().is_empty() {
+                bucket.remove();
+            }
+        }
+    }
+
+    pub fn in_chunk(&self, chunk: ChunkCoords) -> impl Iterator<Item = &Entity> {
+        self.chunks
+            .get(&chunk)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.entities.get(id))
+    }
+
+    pub fn in_chunks<'a>(
+        &'a self,
+        chunks: impl IntoIterator<Item = &'a ChunkCoords>,
+    ) -> impl Iterator<Item = &'a Entity> {
+        chunks.into_iter().flat_map(|chunk| self.in_chunk(*chunk))
+    }
+
+    /// The entities in the chunks overlapping the square of `range` around `position`. This
+    /// is a superset of the entities in range, callers still check the actual distance.
+    pub fn near(&self, position: Vec2, range: f32) -> impl Iterator<Item = &Entity> {
+        let (start_x, start_y) =
+            get_chunk_coords_of(Vec2::new(position.x - range, position.y - range));
+        let (end_x, end_y) = get_chunk_coords_of(Vec2::new(position.x + range, position.y + range));
+
+        (start_y..=end_y)
+            .flat_map(move |y| (start_x..=end_x).map(move |x| (x, y)))
+            .flat_map(|chunk| self.in_chunk(chunk))
+    }
+}
@@ -1,9 +1,10 @@
 use noise::{NoiseFn, OpenSimplex};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use std::iter;
 use uuid::Uuid;
 
-use crate::config::*;
 use crate::model::{Entity, EntityType, Vec2};
+use crate::settings::GameSettings;
 
 pub struct OctavedNoise {
     base: OpenSimplex,
@@ -61,23 +62,41 @@ pub enum TileType {
     Ice,
 }
 
+impl TileType {
+    pub fn is_walkable(&self) -> bool {
+        !matches!(self, TileType::DeepWater | TileType::Water)
+    }
+}
+
 const ELEV_NOISE_SCALE: f64 = 0.04;
 const ENV_NOISE_SCALE: f64 = 0.01;
 const ENTITY_NOISE_SCALE: f64 = 1.0;
+const VARIANT_NOISE_SCALE: f64 = 0.37;
 
 const DEEP_SEA_LEVEL: f64 = 0.40;
 const SEA_LEVEL: f64 = 0.45;
 const BEACH_LEVEL: f64 = 0.48;
+const MOUNTAIN_LEVEL: f64 = 0.6;
+
+/// Below this variant noise a tile spawns its rarer entity, e.g. a small stone instead of a
+/// big one.
+const RARE_VARIANT_LEVEL: f64 = 0.4;
 
 pub struct TerrainGenerator {
     elev_noise: OctavedNoise,
     temp_noise: OctavedNoise,
     humid_noise: OctavedNoise,
     entity_noise: OctavedNoise,
+    variant_noise: OctavedNoise,
+
+    world_size: i32,
+    water_edge_size: i32,
+    entity_spawn_rate: f64,
+    entity_health: i32,
 }
 
 impl TerrainGenerator {
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u32, settings: &GameSettings) -> Self {
         Self {
             elev_noise: OctavedNoise::new(seed, 5, 0.5, 2.0, ELEV_NOISE_SCALE),
             temp_noise: OctavedNoise::new(seed.wrapping_add(420), 3, 0.5, 4.0, ENV_NOISE_SCALE),
@@ -89,11 +108,26 @@ impl TerrainGenerator {
                 2.0,
                 ENTITY_NOISE_SCALE,
             ),
+            variant_noise: OctavedNoise::new(
+                seed.wrapping_add(4242),
+                2,
+                0.5,
+                2.0,
+                VARIANT_NOISE_SCALE,
+            ),
+
+            world_size: settings.world_size,
+            water_edge_size: settings.water_edge_size,
+            entity_spawn_rate: settings.entity_spawn_rate,
+            entity_health: settings.max_world_entity_health,
         }
     }
 
     fn get_tile_from_environment(&self, e: f64, t: f64, h: f64) -> TileType {
         match (t, h) {
+            // Mountains, unless frozen
+            (t, _) if e > MOUNTAIN_LEVEL && t > 0.1 => TileType::Stone,
+
             // Hot, Dry
             (t, h) if t > 0.55 && h < 0.5 => TileType::Desert,
             (t, h) if t > 0.55 && h < 0.6 => TileType::Savanna,
@@ -122,13 +156,13 @@ impl TerrainGenerator {
     }
 
     pub fn get_tile(&self, x: f64, y: f64) -> TileType {
-        const HWF: f64 = WORLD_SIZE as f64 / 2.0;
-        const WATER_EDGE_SIZE_F: f64 = WATER_EDGE_SIZE as f64;
+        let hwf = self.world_size as f64 / 2.0;
+        let water_edge_size_f = self.water_edge_size as f64;
 
         let mut e = self.elev_noise.get(x, y);
-        let s = (x - HWF).abs().max((y - HWF).abs()) - HWF + WATER_EDGE_SIZE_F;
+        let s = (x - hwf).abs().max((y - hwf).abs()) - hwf + water_edge_size_f;
         if s > 0.0 {
-            e -= s / WATER_EDGE_SIZE_F;
+            e -= s / water_edge_size_f;
         }
 
         let mut t = self.temp_noise.get(x, y);
@@ -154,7 +188,7 @@ impl TerrainGenerator {
     }
 
     fn should_spawn_entity(&self, x: f64, y: f64) -> bool {
-        self.entity_noise.get(x, y) < WORLD_ENTITY_SPAWN_RATE
+        self.entity_noise.get(x, y) < self.entity_spawn_rate
     }
 
     fn get_entity_from_tile(&self, x: f64, y: f64, tile: TileType) -> Option<Entity> {
@@ -162,9 +196,14 @@ impl TerrainGenerator {
             return None;
         }
 
+        let rare = self.variant_noise.get(x, y) < RARE_VARIANT_LEVEL;
+
         let entity_type = match tile {
+            TileType::Grass if rare => Some(EntityType::Bush),
             TileType::Grass => Some(EntityType::TallGrass),
+            TileType::Forest if rare => Some(EntityType::Bush),
             TileType::Forest => Some(EntityType::ForestTree),
+            TileType::Tundra if rare => Some(EntityType::Stone),
             TileType::Tundra => Some(EntityType::SpruceTree),
             TileType::Jungle => Some(EntityType::JungleTree),
             TileType::Desert => Some(EntityType::Cactus),
@@ -172,6 +211,7 @@ impl TerrainGenerator {
             TileType::Ice => Some(EntityType::IceSpike),
             TileType::Savanna => Some(EntityType::TreeStump),
             TileType::Swamp => Some(EntityType::TreeLog),
+            TileType::Stone if rare => Some(EntityType::Stone),
             TileType::Stone => Some(EntityType::BigStone),
             _ => None,
         };
@@ -181,7 +221,7 @@ impl TerrainGenerator {
                 Uuid::new_v4(),
                 Vec2::new(x as f32, y as f32),
                 ty,
-                MAX_WORLD_ENTITY_HEALTH,
+                self.entity_health,
             ));
         }
 
@@ -197,8 +237,173 @@ impl TerrainGenerator {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "CompactChunk", try_from = "CompactChunk")]
 pub struct TerrainChunk {
     pub position: Vec2,
     pub contents: Vec<TileType>,
 }
+
+/// How chunks are sent: the distinct tiles of the chunk, and its contents in row order as
+/// runs of `palette index, run length` pairs.
+#[derive(Serialize, Deserialize)]
+struct CompactChunk {
+    position: Vec2,
+    #[serde(serialize_with = "serialize_tiles")]
+    palette: Vec<TileType>,
+    runs: Vec<u8>,
+}
+
+impl From<TerrainChunk> for CompactChunk {
+    fn from(chunk: TerrainChunk) -> Self {
+        let mut palette: Vec<TileType> = vec![];
+        let mut runs: Vec<u8> = vec![];
+
+        for tile in chunk.contents {
+            let index = match palette.iter().position(|t| *t == tile) {
+                Some(index) => index as u8,
+                _ => {
+                    palette.push(tile);
+                    (palette.len() - 1) as u8
+                }
+            };
+
+            match runs.as_mut_slice() {
+                [.., last, length] if *last == index && *length < u8::MAX => *length += 1,
+                _ => runs.extend([index, 1]),
+            }
+        }
+
+        Self {
+            position: chunk.position,
+            palette,
+            runs,
+        }
+    }
+}
+
+impl TryFrom<CompactChunk> for TerrainChunk {
+    type Error = &'static str;
+
+    fn try_from(chunk: CompactChunk) -> Result<Self, Self::Error> {
+        let mut contents = vec![];
+
+        for run in chunk.runs.chunks(2) {
+            let [index, length] = *run else {
+                return Err("incomplete run");
+            };
+            let tile = chunk
+                .palette
+                .get(index as usize)
+                .ok_or("palette index out of range")?;
+
+            contents.extend(iter::repeat_n(*tile, length as usize));
+        }
+
+        Ok(Self {
+            position: chunk.position,
+            contents,
+        })
+    }
+}
+
+/// Tiles are sent by name in JSON and by index in binary encodings.
+fn serialize_tiles<S: Serializer>(tiles: &[TileType], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        tiles.serialize(serializer)
+    } else {
+        serializer.collect_seq(tiles.iter().map(|tile| *tile as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_TILES: [TileType; 13] = [
+        TileType::DeepWater,
+        TileType::Water,
+        TileType::Beach,
+        TileType::Grass,
+        TileType::Forest,
+        TileType::Desert,
+        TileType::Savanna,
+        TileType::Tundra,
+        TileType::Snow,
+        TileType::Stone,
+        TileType::Jungle,
+        TileType::Swamp,
+        TileType::Ice,
+    ];
+
+    fn chunk(contents: Vec<TileType>) -> TerrainChunk {
+        TerrainChunk {
+            position: Vec2::new(8.0, 16.0),
+            contents,
+        }
+    }
+
+    fn assert_round_trips(chunk: &TerrainChunk) {
+        let json: TerrainChunk =
+            serde_json::from_str(&serde_json::to_string(chunk).unwrap()).unwrap();
+        let msgpack: TerrainChunk =
+            rmp_serde::from_slice(&rmp_serde::to_vec_named(chunk).unwrap()).unwrap();
+
+        for decoded in [json, msgpack] {
+            assert_eq!(decoded.contents, chunk.contents);
+            assert_eq!(decoded.position.x, chunk.position.x);
+            assert_eq!(decoded.position.y, chunk.position.y);
+        }
+    }
+
+    #[test]
+    fn single_tile_chunk_is_one_run() {
+        let chunk = chunk(vec![TileType::Grass; 64]);
+
+        let compact = CompactChunk::from(chunk.clone());
+        assert_eq!(compact.palette, vec![TileType::Grass]);
+        assert_eq!(compact.runs, vec![0, 64]);
+
+        assert_round_trips(&chunk);
+    }
+
+    #[test]
+    fn all_different_neighbours_get_a_run_each() {
+        let chunk = chunk(ALL_TILES.iter().copied().cycle().take(64).collect());
+
+        let compact = CompactChunk::from(chunk.clone());
+        assert_eq!(compact.palette, ALL_TILES.to_vec());
+        assert_eq!(compact.runs.len(), 2 * 64);
+
+        assert_round_trips(&chunk);
+    }
+
+    #[test]
+    fn long_runs_are_split_at_the_run_length_limit() {
+        let mut contents = vec![TileType::Water; 600];
+        contents.extend([TileType::Beach; 3]);
+        let chunk = chunk(contents);
+
+        let compact = CompactChunk::from(chunk.clone());
+        assert_eq!(compact.runs, vec![0, 255, 0, 255, 0, 90, 1, 3]);
+
+        assert_round_trips(&chunk);
+    }
+
+    #[test]
+    fn broken_runs_are_rejected() {
+        let incomplete = CompactChunk {
+            position: Vec2::new(0.0, 0.0),
+            palette: vec![TileType::Grass],
+            runs: vec![0, 4, 0],
+        };
+        assert!(TerrainChunk::try_from(incomplete).is_err());
+
+        let out_of_range = CompactChunk {
+            position: Vec2::new(0.0, 0.0),
+            palette: vec![TileType::Grass],
+            runs: vec![1, 4],
+        };
+        assert!(TerrainChunk::try_from(out_of_range).is_err());
+    }
+}